@@ -0,0 +1,80 @@
+use crate::*;
+
+/// The role of a disk in the storage of a filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiskRole {
+    /// the only disk of the filesystem
+    Single,
+    /// a member of a mirror (RAID1, RAID10, btrfs raid1, etc.)
+    Mirror,
+    /// a member of a striped set without redundancy (RAID0)
+    Stripe,
+    /// a member of a striped set with parity (RAID4, RAID5, RAID6)
+    Parity,
+    /// a member of a concatenation (md linear, btrfs single)
+    Span,
+    /// a physical volume of a LVM volume group
+    PhysicalVolume,
+    /// a fast device caching a slower one (bcache, dm-cache, dm-writecache)
+    Cache,
+    /// a slow device whose data is cached by a faster one
+    Cached,
+    /// a member of a multi-device setup, without more specific role
+    Member,
+}
+
+/// A physical disk a filesystem is stored on
+#[derive(Debug, Clone)]
+pub struct BackingDisk {
+    pub disk: Disk,
+    pub role: DiskRole,
+}
+
+impl Mount {
+    /// a synthetic code trying to express the essence of the type of media,
+    /// as [Disk::disk_type] for the disk of the mount, but "hybr" when the
+    /// filesystem is stored on both HDD and SSD (eg an SSD cache over a HDD).
+    /// This code is for humans and may change in future minor versions.
+    pub fn disk_type(&self) -> &'static str {
+        let code = self.disk.as_ref().map_or("", |disk| disk.disk_type());
+        if matches!(code, "HDD" | "SSD" | "") && self.is_on_mixed_media() {
+            "hybr"
+        } else {
+            code
+        }
+    }
+    /// tell whether the backing disks of the mount are of both
+    /// rotational and non rotational types
+    fn is_on_mixed_media(&self) -> bool {
+        let has = |rotational: bool| {
+            self.backing_disks
+                .iter()
+                .any(|bd| bd.disk.rotational == Some(rotational))
+        };
+        has(true) && has(false)
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_mixed_disk_type() {
+    let disk = |name: &str, rotational: bool| Disk {
+        rotational: Some(rotational),
+        removable: Some(false),
+        ..Disk::with_name(name)
+    };
+    let mut mount = Mount {
+        disk: Some(disk("bcache0", true)),
+        backing_disks: vec![BackingDisk {
+            disk: disk("sdd", true),
+            role: DiskRole::Cached,
+        }],
+        ..Mount::from_mountinfo_line("41 25 252:0 / /data rw - ext4 /dev/bcache0 rw")
+    };
+    assert_eq!(mount.disk_type(), "HDD");
+    mount.backing_disks.push(BackingDisk {
+        disk: disk("nvme0n1", false),
+        role: DiskRole::Cache,
+    });
+    assert_eq!(mount.disk_type(), "hybr");
+}
@@ -0,0 +1,230 @@
+use {
+    crate::*,
+    std::path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// Why a mount isn't the original mount of its device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundReason {
+    /// a subvolume (eg btrfs) of a filesystem which is also mounted elsewhere
+    Subvolume,
+    /// a bind mount of a subdirectory of the filesystem
+    SubdirBind,
+    /// a bind mount of the whole mounted filesystem (same root)
+    WholeFsBind,
+}
+
+/// The origin of a bind mount: the mount exposing the same filesystem
+/// at a higher (or the same) level, and the path, in this filesystem,
+/// of the directory which was bound.
+///
+/// For example, after `mount --bind /srv/data/projects /home/me/projects`,
+/// the origin of the `/home/me/projects` mount is the mount of `/srv`
+/// (assuming it's a filesystem), and the subpath is `data/projects`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BindSource {
+    pub origin_id: Option<MountId>,
+    pub origin_mount_point: PathBuf,
+    /// path of the bound directory, relative to the mount point of the origin
+    pub subpath: PathBuf,
+}
+
+impl BindSource {
+    /// the path of the bound directory, through the origin mount
+    pub fn source_path(&self) -> PathBuf {
+        join(&self.origin_mount_point, &self.subpath)
+    }
+}
+
+impl MountInfo {
+    /// Translate a path under the mount point of this bind mount into
+    /// the equivalent path under the mount point of its origin.
+    ///
+    /// Return None if it's not a bind mount or if the path isn't under
+    /// its mount point.
+    pub fn path_in_origin(
+        &self,
+        path: &Path,
+    ) -> Option<PathBuf> {
+        let source = self.bind_source.as_ref()?;
+        let relative = path.strip_prefix(&self.mount_point).ok()?;
+        Some(join(&source.source_path(), relative))
+    }
+    /// Translate a path under the mount point of the origin of this bind
+    /// mount into the equivalent path under its own mount point.
+    ///
+    /// Return None if it's not a bind mount or if the path isn't under
+    /// the bound directory.
+    pub fn path_from_origin(
+        &self,
+        path: &Path,
+    ) -> Option<PathBuf> {
+        let source = self.bind_source.as_ref()?;
+        let relative = path.strip_prefix(source.source_path()).ok()?;
+        Some(join(&self.mount_point, relative))
+    }
+}
+
+/// join without adding a trailing separator when the relative path is empty
+fn join(
+    base: &Path,
+    relative: &Path,
+) -> PathBuf {
+    if relative.as_os_str().is_empty() {
+        base.to_path_buf()
+    } else {
+        base.join(relative)
+    }
+}
+
+/// Set the `bound`, `bound_reason` and `bind_source` fields of the
+/// mount infos, which must have a correct `root`.
+///
+/// Among the mounts of a device, the original one is the one with the
+/// shortest root, then the shortest mount point, then the lowest id,
+/// then the first listed. The other ones are bound, and their origin is
+/// the mount of the device whose root contains their root, chosen with
+/// the same order.
+#[cfg(target_os = "linux")]
+pub(crate) fn compute_bound(infos: &mut [MountInfo]) {
+    let results = bound_reasons(infos);
+    for (idx, (reason, origin)) in results.into_iter().enumerate() {
+        let bind_source = origin.map(|origin| {
+            let origin = &infos[origin];
+            BindSource {
+                origin_id: origin.id,
+                origin_mount_point: origin.mount_point.clone(),
+                subpath: infos[idx]
+                    .root
+                    .strip_prefix(&origin.root)
+                    .unwrap_or(Path::new(""))
+                    .to_path_buf(),
+            }
+        });
+        let info = &mut infos[idx];
+        info.bound = reason.is_some();
+        info.bound_reason = reason;
+        info.bind_source = bind_source;
+    }
+}
+
+/// Return, for each mount info, why it's bound (if it is) and the
+/// index of its origin (if it's found)
+#[cfg(target_os = "linux")]
+fn bound_reasons(infos: &[MountInfo]) -> Vec<(Option<BoundReason>, Option<usize>)> {
+    let rank = |idx: usize| {
+        let info = &infos[idx];
+        (
+            info.root.components().count(),
+            info.mount_point.components().count(),
+            info.id.unwrap_or(MountId::MAX),
+            idx,
+        )
+    };
+    (0..infos.len())
+        .map(|idx| {
+            let info = &infos[idx];
+            let better_same_dev = || {
+                (0..infos.len())
+                    .filter(|&other| infos[other].dev == info.dev && rank(other) < rank(idx))
+            };
+            if better_same_dev().next().is_none() {
+                return (None, None); // the original one
+            }
+            let origin = better_same_dev()
+                .filter(|&other| info.root.starts_with(&infos[other].root))
+                .min_by_key(|&other| rank(other));
+            let is_subvolume = info
+                .option_value("subvol")
+                .is_some_and(|subvol| Path::new(subvol) == info.root);
+            let reason = match origin {
+                Some(origin) if infos[origin].root == info.root => BoundReason::WholeFsBind,
+                _ if is_subvolume => BoundReason::Subvolume,
+                _ => BoundReason::SubdirBind,
+            };
+            (Some(reason), origin)
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_bound_btrfs() {
+    use std::str::FromStr;
+    // the subvolumes are listed before the root, and /home has the lowest id
+    let mut infos: Vec<MountInfo> = [
+        "30 25 0:30 /@home /home rw - btrfs /dev/sda1 rw,subvol=/@home",
+        "31 25 0:30 /@snapshots /.snapshots rw - btrfs /dev/sda1 rw,subvol=/@snapshots",
+        "32 25 0:30 /@home/me/www /srv/www rw - btrfs /dev/sda1 rw,subvol=/@home",
+        "35 1 0:30 /@ / rw - btrfs /dev/sda1 rw,subvol=/@",
+    ]
+    .iter()
+    .map(|line| MountInfo::from_str(line).unwrap())
+    .collect();
+    compute_bound(&mut infos);
+    assert!(!infos[3].bound);
+    assert_eq!(infos[0].bound_reason, Some(BoundReason::Subvolume));
+    assert_eq!(infos[0].bind_source, None);
+    assert_eq!(infos[1].bound_reason, Some(BoundReason::Subvolume));
+    assert_eq!(infos[2].bound_reason, Some(BoundReason::SubdirBind));
+    assert_eq!(
+        infos[2].bind_source.as_ref().unwrap().source_path(),
+        Path::new("/home/me/www")
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_bind_sources() {
+    use std::str::FromStr;
+    let mut infos: Vec<MountInfo> = [
+        "25 1 8:1 / / rw - ext4 /dev/sda1 rw",
+        "40 25 8:2 / /srv rw - ext4 /dev/sda2 rw",
+        "41 25 8:2 /data/projects /home/me/projects rw - ext4 /dev/sda2 rw",
+        "42 25 8:2 /data/projects/lfs /home/me/lfs rw - ext4 /dev/sda2 rw",
+        "43 25 8:2 / /mnt/srv rw - ext4 /dev/sda2 rw",
+    ]
+    .iter()
+    .map(|line| MountInfo::from_str(line).unwrap())
+    .collect();
+    compute_bound(&mut infos);
+    assert!(!infos[1].bound);
+    assert_eq!(infos[1].bind_source, None);
+    assert_eq!(infos[2].bound_reason, Some(BoundReason::SubdirBind));
+    assert_eq!(infos[4].bound_reason, Some(BoundReason::WholeFsBind));
+    let source = infos[2].bind_source.as_ref().unwrap();
+    assert_eq!(source.origin_id, Some(40));
+    assert_eq!(source.subpath, Path::new("data/projects"));
+    assert_eq!(source.source_path(), Path::new("/srv/data/projects"));
+    // the origin is the mount with the shortest root, not the last bind
+    assert_eq!(infos[3].bind_source.as_ref().unwrap().origin_id, Some(40));
+    assert_eq!(
+        infos[4].bind_source.as_ref().unwrap().subpath,
+        Path::new("")
+    );
+    assert_eq!(
+        infos[2].path_in_origin(Path::new("/home/me/projects/a/b.txt")),
+        Some(PathBuf::from("/srv/data/projects/a/b.txt"))
+    );
+    assert_eq!(
+        infos[2].path_in_origin(Path::new("/home/me/projects")),
+        Some(PathBuf::from("/srv/data/projects"))
+    );
+    assert_eq!(infos[2].path_in_origin(Path::new("/home/me/other")), None);
+    assert_eq!(
+        infos[2].path_from_origin(Path::new("/srv/data/projects/a")),
+        Some(PathBuf::from("/home/me/projects/a"))
+    );
+    assert_eq!(
+        infos[2].path_from_origin(Path::new("/srv/data/other")),
+        None
+    );
+    assert_eq!(
+        infos[4].path_from_origin(Path::new("/srv/data")),
+        Some(PathBuf::from("/mnt/srv/data"))
+    );
+    assert_eq!(infos[1].path_in_origin(Path::new("/srv/data")), None);
+}
@@ -22,7 +22,7 @@ use {
     },
 };
 /// Id of a volume, can be found using GetVolumeInformationW
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct DeviceId {
     pub serial: u32,
 }
@@ -1,3 +1,6 @@
+#[cfg(target_os = "linux")]
+use crate::LoopInfo;
+
 /// what we have most looking like a physical device
 #[derive(Debug, Clone)]
 pub struct Disk {
@@ -31,6 +34,10 @@ pub struct Disk {
     /// whether it's a remote disk
     #[cfg(windows)]
     pub remote: bool,
+
+    /// the backing file, when it's a loop device
+    #[cfg(target_os = "linux")]
+    pub loop_info: Option<LoopInfo>,
 }
 
 impl Disk {
@@ -55,4 +62,31 @@ impl Disk {
             }
         }
     }
+    /// tell whether the data of the disk is in a file: a disk image
+    /// or, on linux, a loop device
+    pub fn is_file_backed(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        if self.loop_info.is_some() {
+            return true;
+        }
+        self.image
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+impl Disk {
+    /// Build a disk with only a name, its other properties being unknown
+    pub(crate) fn with_name(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rotational: None,
+            removable: None,
+            read_only: None,
+            ram: false,
+            image: false,
+            lvm: false,
+            crypted: false,
+            loop_info: None,
+        }
+    }
 }
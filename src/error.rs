@@ -23,12 +23,20 @@ pub enum Error {
         path: std::path::PathBuf,
     },
 
+    #[snafu(display("Could not poll the mount table"))]
+    #[cfg(target_os = "linux")]
+    CantPoll { source: std::io::Error },
+
     #[snafu(display("Could not parse mountinfo"))]
     #[cfg(target_os = "linux")]
     ParseMountInfo {
         source: crate::linux::ParseMountInfoError,
     },
 
+    #[snafu(display("Could not parse fstab"))]
+    #[cfg(target_os = "linux")]
+    ParseFstab { source: crate::ParseFstabEntryError },
+
     #[snafu(display("Unexpected format"))]
     UnexpectedFormat,
 
@@ -0,0 +1,716 @@
+use {
+    crate::*,
+    snafu::prelude::*,
+    std::{
+        fmt,
+        path::{
+            Path,
+            PathBuf,
+        },
+        str::FromStr,
+    },
+};
+
+/// fstab options which are only used by userspace tools and
+/// thus never appear in the options of a mount
+static USERSPACE_ONLY_OPTIONS: &[&str] = &[
+    "auto", "noauto", "user", "nouser", "users", "owner", "group", "nofail", "_netdev", "comment",
+    "sw", "pri", "bg", "fg", "bind", "rbind", "loop",
+];
+
+/// the options `defaults` stands for in fstab
+static DEFAULTS_OPTIONS: &[&str] = &["rw", "suid", "dev", "exec", "auto", "nouser", "async"];
+
+/// generic options of which only one of a group applies, the last declared
+static EXCLUSIVE_OPTIONS: &[&[&str]] = &[
+    &["rw", "ro"],
+    &["suid", "nosuid"],
+    &["dev", "nodev"],
+    &["exec", "noexec"],
+    &["async", "sync"],
+    &["atime", "relatime", "noatime", "strictatime"],
+    &["diratime", "nodiratime"],
+    &["mand", "nomand"],
+    &["lazytime", "nolazytime"],
+    &["symfollow", "nosymfollow"],
+];
+
+/// generic options which are applied by the kernel when no other one of their
+/// group is given, and thus don't appear in the options of a mount
+static KERNEL_DEFAULT_OPTIONS: &[&str] = &[
+    "rw",
+    "suid",
+    "dev",
+    "exec",
+    "async",
+    "atime",
+    "relatime",
+    "diratime",
+    "nomand",
+    "nolazytime",
+    "symfollow",
+];
+
+/// filesystem specific options which the kernel doesn't show when they
+/// have their default value
+static HIDDEN_WHEN_DEFAULT_OPTIONS: &[&str] = &[
+    "errors",
+    "commit",
+    "data",
+    "uid",
+    "gid",
+    "umask",
+    "fmask",
+    "dmask",
+    "mode",
+    "codepage",
+    "iocharset",
+    "shortname",
+];
+
+/// types of filesystems which fsck checks at boot (the fsck of
+/// btrfs and xfs does nothing)
+static FSCK_FS_TYPES: &[&str] = &[
+    "ext2", "ext3", "ext4", "vfat", "exfat", "f2fs", "jfs", "reiserfs", "hfsplus",
+];
+
+/// options whose value is an octal mask
+static OCTAL_OPTIONS: &[&str] = &["umask", "fmask", "dmask", "mode"];
+
+/// The first field of a fstab line, telling what is mounted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsSpec {
+    Uuid(String),
+    Label(String),
+    PartUuid(String),
+    PartLabel(String),
+    /// a path (eg "/dev/sda1") or anything not tagged, like a
+    /// remote share ("server:/export") or a pseudo-filesystem ("tmpfs")
+    Path(String),
+}
+
+/// An entry of /etc/fstab
+#[derive(Debug, Clone)]
+pub struct FstabEntry {
+    pub spec: FsSpec,
+    /// the device the spec resolves to (eg "/dev/sda1"), when known
+    pub device: Option<String>,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub options: Vec<MountOption>,
+    pub dump: u32,
+    pub pass: u32,
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(display("Could not parse {line} as fstab entry"))]
+pub struct ParseFstabEntryError {
+    line: String,
+}
+
+/// A mount whose options differ from the ones declared in fstab
+#[derive(Debug, Clone)]
+pub struct FstabOptionsMismatch<'f, 'm> {
+    pub entry: &'f FstabEntry,
+    pub mount: &'m Mount,
+    /// options declared in fstab but not found on the mount
+    pub missing: Vec<MountOption>,
+    /// generic options (eg "ro", "nosuid", "noatime") found on the
+    /// mount while fstab declares otherwise
+    pub unexpected: Vec<MountOption>,
+}
+
+/// The result of the comparison of fstab with the live mounts
+#[derive(Debug, Clone)]
+pub struct FstabReconciliation<'f, 'm> {
+    /// fstab entries with no mount of their device on their mount point
+    pub not_mounted: Vec<&'f FstabEntry>,
+    /// storage mounts (on a block device or remote) not declared in fstab
+    pub undeclared: Vec<&'m Mount>,
+    /// mounts whose options differ from the ones of their fstab entry
+    pub options_mismatches: Vec<FstabOptionsMismatch<'f, 'm>>,
+}
+
+impl FromStr for FsSpec {
+    type Err = std::convert::Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = sys::decode_string(s);
+        let spec = match s.split_once('=') {
+            Some(("UUID", v)) => Self::Uuid(v.to_string()),
+            Some(("LABEL", v)) => Self::Label(v.to_string()),
+            Some(("PARTUUID", v)) => Self::PartUuid(v.to_string()),
+            Some(("PARTLABEL", v)) => Self::PartLabel(v.to_string()),
+            _ => Self::Path(s),
+        };
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for FsSpec {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match self {
+            Self::Uuid(v) => write!(f, "UUID={}", sys::encode_string(v)),
+            Self::Label(v) => write!(f, "LABEL={}", sys::encode_string(v)),
+            Self::PartUuid(v) => write!(f, "PARTUUID={}", sys::encode_string(v)),
+            Self::PartLabel(v) => write!(f, "PARTLABEL={}", sys::encode_string(v)),
+            Self::Path(v) => write!(f, "{}", sys::encode_string(v)),
+        }
+    }
+}
+
+impl FsSpec {
+    /// Return the device this spec designates (eg "/dev/sda1"), using
+    /// the mappings read in /dev/disk/by-*
+    pub fn resolve(
+        &self,
+        labellings: &FsSpecLabellings,
+    ) -> Option<String> {
+        // uuids are written in various cases but labels are case sensitive
+        let (value, labellings, ignore_case) = match self {
+            Self::Uuid(v) => (v, &labellings.by_uuid, true),
+            Self::Label(v) => (v, &labellings.by_label, false),
+            Self::PartUuid(v) => (v, &labellings.by_partuuid, true),
+            Self::PartLabel(v) => (v, &labellings.by_partlabel, false),
+            Self::Path(path) => {
+                return path.starts_with("/dev/").then(|| path.clone());
+            }
+        };
+        labellings
+            .as_deref()?
+            .iter()
+            .find(|l| {
+                if ignore_case {
+                    l.label.eq_ignore_ascii_case(value)
+                } else {
+                    l.label == value.as_str()
+                }
+            })
+            .map(|l| l.fs_name.clone())
+    }
+}
+
+/// The mappings needed to resolve fs specs
+#[derive(Debug, Clone, Default)]
+pub struct FsSpecLabellings {
+    pub by_uuid: Option<Vec<Labelling>>,
+    pub by_label: Option<Vec<Labelling>>,
+    pub by_partuuid: Option<Vec<Labelling>>,
+    pub by_partlabel: Option<Vec<Labelling>>,
+}
+
+impl FsSpecLabellings {
+    /// Read the mappings in /dev/disk/by-*, taking the system
+    /// root of the options into account
+    pub fn read(options: &ReadOptions) -> Self {
+        let dev_disk = options.system_path("/dev/disk");
+        Self {
+            by_uuid: read_by_in(&dev_disk, "uuid").ok(),
+            by_label: read_by_in(&dev_disk, "label").ok(),
+            by_partuuid: read_by_in(&dev_disk, "partuuid").ok(),
+            by_partlabel: read_by_in(&dev_disk, "partlabel").ok(),
+        }
+    }
+}
+
+impl FromStr for FstabEntry {
+    type Err = ParseFstabEntryError;
+    /// parse a fstab line, based on `man 5 fstab`
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        (|| {
+            let mut tokens = line.split_whitespace();
+            let spec: FsSpec = tokens.next()?.parse().ok()?;
+            let mount_point = PathBuf::from(sys::decode_os_string(tokens.next()?));
+            let fs_type = sys::decode_string(tokens.next()?);
+            let options = match tokens.next() {
+                Some(options) => MountOption::parse_list(options),
+                None => vec![MountOption::new("defaults", None)],
+            };
+            let dump = tokens.next().map_or(Some(0), |t| t.parse().ok())?;
+            let pass = tokens.next().map_or(Some(0), |t| t.parse().ok())?;
+            Some(Self {
+                device: None,
+                spec,
+                mount_point,
+                fs_type,
+                options,
+                dump,
+                pass,
+            })
+        })()
+        .with_context(|| ParseFstabEntrySnafu { line })
+    }
+}
+
+impl fmt::Display for FstabEntry {
+    /// write the entry as a fstab line
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        let options = if self.options.is_empty() {
+            "defaults".to_string()
+        } else {
+            encode_options_list(&self.options)
+        };
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            self.spec,
+            sys::encode_path(&self.mount_point),
+            sys::encode_string(&self.fs_type),
+            options,
+            self.dump,
+            self.pass,
+        )
+    }
+}
+
+impl FstabEntry {
+    /// tell whether the option (eg "noauto", "ro") is declared
+    pub fn has_option(
+        &self,
+        name: &str,
+    ) -> bool {
+        self.options.iter().any(|o| o.name == name)
+    }
+    /// tell whether the entry is a swap declaration, which
+    /// can't be found among mounts
+    pub fn is_swap(&self) -> bool {
+        self.fs_type == "swap"
+    }
+    /// Return the options which are declared in the entry but not
+    /// found on the mount, ignoring options only relevant to userspace
+    /// and the defaults applied by the kernel
+    fn missing_options(
+        &self,
+        mount: &Mount,
+    ) -> Vec<MountOption> {
+        let found = normalize_options(mount.info.options());
+        normalize_options(&self.options)
+            .into_iter()
+            .filter(|o| !is_applied(o, &found))
+            .collect()
+    }
+    /// Return the generic options found on the mount but not declared
+    /// in the entry, ignoring the defaults applied by the kernel
+    fn unexpected_options(
+        &self,
+        mount: &Mount,
+    ) -> Vec<MountOption> {
+        let declared = normalize_options(&self.options);
+        // the options of the mount come last, as they prevail over
+        // the ones of the superblock
+        let generic = mount
+            .info
+            .super_options
+            .iter()
+            .filter(|o| is_generic(o))
+            .chain(mount.info.mount_options.iter());
+        normalize_options(generic)
+            .into_iter()
+            .filter(|o| !declared.iter().any(|d| same_option(d, o)))
+            .collect()
+    }
+}
+
+fn is_generic(option: &MountOption) -> bool {
+    EXCLUSIVE_OPTIONS
+        .iter()
+        .any(|group| group.contains(&option.name.as_str()))
+}
+
+/// Normalize a list of options, so that the options of a fstab entry
+/// can be compared with the ones of a mount: `defaults` is expanded,
+/// only the last of exclusive options is kept, options only relevant
+/// to userspace and the defaults applied by the kernel are removed
+fn normalize_options<'o, I: IntoIterator<Item = &'o MountOption>>(options: I) -> Vec<MountOption> {
+    let mut normalized: Vec<MountOption> = Vec::new();
+    let expanded = options.into_iter().flat_map(|o| {
+        if o.name == "defaults" {
+            DEFAULTS_OPTIONS
+                .iter()
+                .map(|name| MountOption::new(*name, None))
+                .collect()
+        } else {
+            vec![o.clone()]
+        }
+    });
+    for option in expanded {
+        let name = option.name.as_str();
+        if USERSPACE_ONLY_OPTIONS.contains(&name) || name.starts_with("x-") {
+            continue;
+        }
+        if let Some(group) = EXCLUSIVE_OPTIONS.iter().find(|g| g.contains(&name)) {
+            normalized.retain(|o| !group.contains(&o.name.as_str()));
+        } else {
+            normalized.retain(|o| o.name != option.name);
+        }
+        normalized.push(option);
+    }
+    normalized.retain(|o| !KERNEL_DEFAULT_OPTIONS.contains(&o.name.as_str()));
+    normalized
+}
+
+/// tell whether a declared option is applied on a mount, given
+/// the normalized options of the mount
+fn is_applied(
+    declared: &MountOption,
+    found: &[MountOption],
+) -> bool {
+    if found.iter().any(|f| same_option(declared, f)) {
+        return true;
+    }
+    let name = declared.name.as_str();
+    if name == "umask" {
+        // the kernel may show the umask as a mask for files and one for dirs
+        let masks: Vec<&MountOption> = found
+            .iter()
+            .filter(|f| f.name == "fmask" || f.name == "dmask")
+            .collect();
+        if !masks.is_empty() {
+            return masks
+                .iter()
+                .all(|mask| same_value(name, declared.value.as_deref(), mask.value.as_deref()));
+        }
+    }
+    // an option not shown by the kernel is assumed to have its default value
+    HIDDEN_WHEN_DEFAULT_OPTIONS.contains(&name) && !found.iter().any(|f| f.name == name)
+}
+
+/// tell whether a declared option is the one found on a mount
+fn same_option(
+    declared: &MountOption,
+    found: &MountOption,
+) -> bool {
+    declared.name == found.name
+        && same_value(
+            &declared.name,
+            declared.value.as_deref(),
+            found.value.as_deref(),
+        )
+}
+
+fn same_value(
+    name: &str,
+    declared: Option<&str>,
+    found: Option<&str>,
+) -> bool {
+    match (declared, found) {
+        (None, _) => true,
+        (Some(a), Some(b)) if name == "subvol" => {
+            a.trim_start_matches('/') == b.trim_start_matches('/')
+        }
+        (Some(a), Some(b)) if OCTAL_OPTIONS.contains(&name) => {
+            match (u32::from_str_radix(a, 8), u32::from_str_radix(b, 8)) {
+                (Ok(a), Ok(b)) => a == b,
+                _ => a == b,
+            }
+        }
+        (a, b) => a == b,
+    }
+}
+
+impl Mount {
+    /// Return a fstab entry declaring this mount, designating the
+    /// filesystem by its UUID when it's known.
+    ///
+    /// A bind mount of a subdirectory is declared as a bind of its source
+    /// path, and a btrfs subvolume keeps its `subvol` option. None is
+    /// returned when the mounted directory can't be designated.
+    ///
+    /// The pass number is 1 for the root and 2 for other block devices
+    /// whose filesystem is checked by fsck at boot, and 0 for the rest.
+    pub fn fstab_entry(&self) -> Option<FstabEntry> {
+        let info = &self.info;
+        let is_fs_root = info.root == Path::new("/");
+        let is_subvolume = info
+            .option_value("subvol")
+            .is_some_and(|subvol| Path::new(subvol) == info.root);
+        if !is_fs_root && !is_subvolume {
+            let source = info.bind_source.as_ref()?;
+            let mut options = vec![MountOption::new("bind", None)];
+            if info.is_read_only() {
+                options.push(MountOption::new("ro", None));
+            }
+            return Some(FstabEntry {
+                spec: FsSpec::Path(source.source_path().to_string_lossy().to_string()),
+                device: None,
+                mount_point: info.mount_point.clone(),
+                fs_type: "none".to_string(),
+                options,
+                dump: 0,
+                pass: 0,
+            });
+        }
+        let spec = match &self.uuid {
+            Some(uuid) => FsSpec::Uuid(uuid.clone()),
+            None => FsSpec::Path(info.fs.clone()),
+        };
+        let on_block_device = info.fs.starts_with("/dev/");
+        let pass = if !on_block_device || !FSCK_FS_TYPES.contains(&info.fs_type.as_str()) {
+            0
+        } else if info.mount_point == Path::new("/") {
+            1
+        } else {
+            2
+        };
+        Some(FstabEntry {
+            device: on_block_device.then(|| info.fs.clone()),
+            spec,
+            mount_point: info.mount_point.clone(),
+            fs_type: info.fs_type.clone(),
+            options: info.options().cloned().collect(),
+            dump: 0,
+            pass,
+        })
+    }
+    /// Return a fstab line declaring this mount, designating the
+    /// filesystem by its UUID when it's known
+    pub fn to_fstab_line(&self) -> Option<String> {
+        self.fstab_entry().map(|entry| entry.to_string())
+    }
+}
+
+/// Read /etc/fstab, taking the system root of the options into
+/// account, and resolve the specs of the entries
+pub fn read_fstab(options: &ReadOptions) -> Result<Vec<FstabEntry>, Error> {
+    let path = options.system_path("/etc/fstab");
+    let content = sys::read_file(&path).context(CantReadFileSnafu { path })?;
+    let labellings = FsSpecLabellings::read(options);
+    let mut entries = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut entry: FstabEntry = line
+            .parse()
+            .map_err(|source| Error::ParseFstab { source })?;
+        entry.device = entry.spec.resolve(&labellings);
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// tell whether the mount is on the given device (eg "/dev/sda1", "/dev/dm-0"
+/// or "/dev/mapper/vg-home"), which may be designated differently in mountinfo
+fn is_on_device(
+    mount: &Mount,
+    device: &str,
+) -> bool {
+    if mount.info.fs == device {
+        return true;
+    }
+    let mut devices = mount.layers.iter().filter(|layer| layer.depth == 1);
+    match device.strip_prefix("/dev/mapper/") {
+        Some(dm_name) => devices.any(|layer| layer.dm_name.as_deref() == Some(dm_name)),
+        None => devices.any(|layer| Some(layer.name.as_str()) == device.strip_prefix("/dev/")),
+    }
+}
+
+/// Compare fstab entries with live mounts, matching them on mount point
+/// and, when the device of an entry is known, on device.
+///
+/// Swap entries are ignored. Entries declared `noauto` are reported as
+/// not mounted when they're not, you may want to filter them out.
+pub fn reconcile_fstab<'f, 'm>(
+    entries: &'f [FstabEntry],
+    mounts: &'m [Mount],
+) -> FstabReconciliation<'f, 'm> {
+    let mut not_mounted = Vec::new();
+    let mut options_mismatches = Vec::new();
+    let mut declared = vec![false; mounts.len()];
+    for entry in entries.iter().filter(|e| !e.is_swap()) {
+        // when several mounts share the mount point, the visible one is the last
+        let Some(idx) = mounts
+            .iter()
+            .rposition(|m| m.info.mount_point == entry.mount_point)
+        else {
+            not_mounted.push(entry);
+            continue;
+        };
+        let mount = &mounts[idx];
+        if let Some(device) = &entry.device {
+            if !is_on_device(mount, device) {
+                not_mounted.push(entry);
+                continue;
+            }
+        }
+        declared[idx] = true;
+        let missing = entry.missing_options(mount);
+        let unexpected = entry.unexpected_options(mount);
+        if !missing.is_empty() || !unexpected.is_empty() {
+            options_mismatches.push(FstabOptionsMismatch {
+                entry,
+                mount,
+                missing,
+                unexpected,
+            });
+        }
+    }
+    let undeclared = mounts
+        .iter()
+        .zip(declared)
+        .filter(|(m, declared)| !declared && (m.disk.is_some() || m.is_remote()))
+        .map(|(m, _)| m)
+        .collect();
+    FstabReconciliation {
+        not_mounted,
+        undeclared,
+        options_mismatches,
+    }
+}
+
+#[test]
+fn test_fstab_entry_from_str() {
+    let entry = FstabEntry::from_str(
+        "UUID=4f1a-22  /home/dys/USB\\040DISK  btrfs  noatime,subvol=@data,x-systemd.automount 0 2",
+    )
+    .unwrap();
+    assert_eq!(entry.spec, FsSpec::Uuid("4f1a-22".to_string()));
+    assert_eq!(entry.mount_point, PathBuf::from("/home/dys/USB DISK"));
+    assert_eq!(entry.fs_type, "btrfs");
+    assert_eq!(entry.options[1], MountOption::new("subvol", Some("@data")));
+    assert_eq!((entry.dump, entry.pass), (0, 2));
+
+    let entry =
+        FstabEntry::from_str("/dev/sdb1 /mnt/usb vfat noauto,x-gvfs-name=USB\\040DISK").unwrap();
+    assert_eq!(entry.options[1].value.as_deref(), Some("USB DISK"));
+
+    let entry = FstabEntry::from_str("tmpfs /tmp tmpfs").unwrap();
+    assert_eq!(entry.spec, FsSpec::Path("tmpfs".to_string()));
+    assert!(entry.has_option("defaults"));
+    assert_eq!((entry.dump, entry.pass), (0, 0));
+
+    assert!(FstabEntry::from_str("/dev/sda1").is_err());
+    assert!(FstabEntry::from_str("/dev/sda1 / ext4 defaults x 1").is_err());
+}
+
+#[test]
+fn test_reconcile_fstab() {
+    let mut entries: Vec<FstabEntry> = [
+        "LABEL=root / ext4 defaults 0 1",
+        "/dev/sda2 /home ext4 noatime,nodev 0 2",
+        "/dev/sda3 /data ext4 defaults,nofail 0 2",
+        "/dev/sda4 none swap sw 0 0",
+        "/dev/sda5 /boot/efi vfat umask=077,errors=remount-ro 0 1",
+        "/dev/sda6 /srv ext4 ro,rw,exec,suid,dev,async,auto,nouser,atime 0 2",
+        "/dev/sda7 /var ext4 defaults 0 2",
+        "/srv/www /var/www none bind 0 0",
+        "UUID=4f1a-22 /mnt/backup ext4 defaults 0 2",
+    ]
+    .iter()
+    .map(|line| line.parse().unwrap())
+    .collect();
+    entries[8].device = Some("/dev/sdc1".to_string());
+    let mount = |line: &str, disk: bool| Mount {
+        disk: disk.then(|| Disk::with_name("sda")),
+        ..Mount::from_mountinfo_line(line)
+    };
+    let mounts = vec![
+        mount("25 1 8:1 / / ro,relatime - ext4 /dev/sda1 rw", true),
+        mount("30 25 0:20 / /sys rw - sysfs sysfs rw", false),
+        mount("40 25 8:2 / /home rw,noatime - ext4 /dev/sda2 rw", true),
+        mount("50 25 8:5 / /media/usb rw - vfat /dev/sdb1 rw", true),
+        mount(
+            "60 25 8:5 / /boot/efi rw,relatime - vfat /dev/sda5 rw,fmask=0077,dmask=0077,codepage=437,iocharset=ascii,shortname=mixed,errors=remount-ro",
+            true,
+        ),
+        mount("61 25 8:6 / /srv rw,relatime - ext4 /dev/sda6 rw", true),
+        mount(
+            "62 25 8:7 / /var rw,nosuid,relatime - ext4 /dev/sda7 rw,commit=30",
+            true,
+        ),
+        mount(
+            "63 62 8:6 /www /var/www rw,relatime - ext4 /dev/sda6 rw",
+            true,
+        ),
+        // not the device declared for this mount point
+        mount("64 25 8:48 / /mnt/backup rw - ext4 /dev/sdd rw", true),
+    ];
+    let reconciliation = reconcile_fstab(&entries, &mounts);
+    assert_eq!(reconciliation.not_mounted.len(), 2);
+    assert_eq!(
+        reconciliation.not_mounted[0].mount_point,
+        PathBuf::from("/data")
+    );
+    assert_eq!(
+        reconciliation.not_mounted[1].mount_point,
+        PathBuf::from("/mnt/backup")
+    );
+    assert_eq!(reconciliation.undeclared.len(), 2);
+    assert_eq!(
+        reconciliation.undeclared[0].info.mount_point,
+        PathBuf::from("/media/usb")
+    );
+    assert_eq!(
+        reconciliation.undeclared[1].info.mount_point,
+        PathBuf::from("/mnt/backup")
+    );
+    let mismatches = &reconciliation.options_mismatches;
+    assert_eq!(mismatches.len(), 3);
+    assert_eq!(mismatches[0].unexpected, vec![MountOption::new("ro", None)]);
+    assert!(mismatches[0].missing.is_empty());
+    assert_eq!(mismatches[1].missing, vec![MountOption::new("nodev", None)]);
+    assert!(mismatches[1].unexpected.is_empty());
+    // default and filesystem specific options aren't mismatches
+    assert_eq!(mismatches[2].mount.info.id, Some(62));
+    assert!(mismatches[2].missing.is_empty());
+    assert_eq!(
+        mismatches[2].unexpected,
+        vec![MountOption::new("nosuid", None)]
+    );
+}
+
+#[test]
+fn test_fstab_line_round_trip() {
+    let lines = [
+        "UUID=4f1a-22 /home/dys/USB\\040DISK btrfs noatime,subvol=@data,x-systemd.automount 0 2",
+        "LABEL=root / ext4 defaults 0 1",
+        "server:/export /mnt/nfs nfs4 rw,hard,vers=4.2 0 0",
+        "/dev/sda4 none swap sw 0 0",
+    ];
+    for line in lines {
+        let entry = FstabEntry::from_str(line).unwrap();
+        assert_eq!(entry.to_string(), line);
+    }
+}
+
+#[test]
+fn test_mount_to_fstab_line() {
+    let mut mount = Mount::from_mountinfo_line(
+        "73 2 0:33 /root / rw,relatime shared:1 - btrfs /dev/vda3 rw,compress=zstd:1,subvol=/root",
+    );
+    assert_eq!(
+        mount.to_fstab_line().unwrap(),
+        "/dev/vda3 / btrfs rw,relatime,compress=zstd:1,subvol=/root 0 0"
+    );
+    mount.uuid = Some("5e2c2d1a-1d5b".to_string());
+    assert_eq!(
+        mount.to_fstab_line().unwrap(),
+        "UUID=5e2c2d1a-1d5b / btrfs rw,relatime,compress=zstd:1,subvol=/root 0 0"
+    );
+    let mount = Mount::from_mountinfo_line("25 1 8:1 / / rw,relatime - ext4 /dev/sda1 rw");
+    assert_eq!(
+        mount.to_fstab_line().unwrap(),
+        "/dev/sda1 / ext4 rw,relatime 0 1"
+    );
+    let mount = Mount::from_mountinfo_line("26 1 0:25 / / rw - overlay overlay rw");
+    assert_eq!(mount.fstab_entry().unwrap().pass, 0);
+    // a bind of a subdirectory, whose source is known or not
+    let mut mount =
+        Mount::from_mountinfo_line("80 25 8:2 /data/www /var/www ro,relatime - ext4 /dev/sda2 rw");
+    assert!(mount.fstab_entry().is_none());
+    mount.info.bind_source = Some(BindSource {
+        origin_id: Some(40),
+        origin_mount_point: PathBuf::from("/srv"),
+        subpath: PathBuf::from("data/www"),
+    });
+    assert_eq!(
+        mount.to_fstab_line().unwrap(),
+        "/srv/data/www /var/www none bind,ro 0 0"
+    );
+}
@@ -1,14 +1,20 @@
+use std::ffi::OsString;
 #[cfg(target_os = "linux")]
 use {
     super::*,
     snafu::prelude::*,
-    std::fs,
+    std::{
+        fs,
+        os::unix::ffi::OsStrExt,
+        path::Path,
+    },
 };
 
 /// the labelling of a file-system, that is the pair (label, fs)
 #[derive(Debug, Clone)]
 pub struct Labelling {
-    pub label: String,
+    /// the label, which isn't guaranteed to be valid UTF-8
+    pub label: OsString,
     pub fs_name: String,
 }
 
@@ -16,6 +22,14 @@ pub fn get_label(
     fs_name: &str,
     labellings: Option<&[Labelling]>,
 ) -> Option<String> {
+    get_label_os(fs_name, labellings).map(|label| label.to_string_lossy().into_owned())
+}
+
+/// return the label of the filesystem, without conversion to UTF-8
+pub fn get_label_os(
+    fs_name: &str,
+    labellings: Option<&[Labelling]>,
+) -> Option<OsString> {
     labellings.as_ref().and_then(|labels| {
         labels
             .iter()
@@ -31,7 +45,17 @@ pub fn get_label(
 /// this information the way lfs-core reads it.
 #[cfg(target_os = "linux")]
 pub fn read_by(by_kind: &str) -> Result<Vec<Labelling>, Error> {
-    let path = format!("/dev/disk/by-{by_kind}");
+    read_by_in(Path::new("/dev/disk"), by_kind)
+}
+
+/// try to read all mappings defined in `<dev_disk>/by-<by_kind>`,
+/// `dev_disk` being usually `/dev/disk`
+#[cfg(target_os = "linux")]
+pub(crate) fn read_by_in(
+    dev_disk: &Path,
+    by_kind: &str,
+) -> Result<Vec<Labelling>, Error> {
+    let path = dev_disk.join(format!("by-{by_kind}"));
     let entries = fs::read_dir(&path).context(CantReadDirSnafu { path })?;
     let labels = entries
         .filter_map(|entry| entry.ok())
@@ -41,7 +65,7 @@ pub fn read_by(by_kind: &str) -> Result<Vec<Labelling>, Error> {
             if !file_type.is_symlink() {
                 return None;
             }
-            let label = sys::decode_string(entry.file_name().to_string_lossy());
+            let label = sys::decode_os_string(entry.file_name().as_bytes());
             let linked_path = fs::read_link(entry.path())
                 .map(|path| path.to_string_lossy().to_string())
                 .ok()?;
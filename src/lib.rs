@@ -18,9 +18,13 @@ The [dysk](https://github.com/Canop/dysk) application is a viewer for lfs-core a
 
 */
 
+mod backing_disk;
+mod bind;
 mod device_id;
 mod disk;
 mod error;
+#[cfg(target_os = "linux")]
+mod fstab;
 mod inodes;
 mod label;
 #[cfg(target_os = "linux")]
@@ -28,27 +32,61 @@ mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 mod mount;
+mod mount_kind;
+mod mount_resolver;
+mod mount_tree;
 mod mountinfo;
+mod mounts_diff;
+mod propagation;
 mod read_options;
+mod shadowing;
+mod stacked;
 mod stats;
+mod storage_layer;
 mod sys;
+mod typed_options;
 #[cfg(windows)]
 mod windows;
 
 pub use {
+    backing_disk::*,
+    bind::*,
     device_id::*,
     disk::*,
     error::*,
     inodes::*,
     label::*,
     mount::*,
+    mount_kind::*,
+    mount_resolver::*,
+    mount_tree::*,
     mountinfo::*,
+    mounts_diff::*,
+    propagation::*,
     read_options::*,
+    shadowing::*,
+    stacked::*,
     stats::*,
+    storage_layer::*,
+    typed_options::*,
 };
 
 #[cfg(target_os = "linux")]
-pub use linux::read_mounts;
+pub use fstab::*;
+#[cfg(target_os = "linux")]
+pub use linux::{
+    BlockDeviceKind,
+    BlockNode,
+    BlockTopology,
+    DmKind,
+    LoopInfo,
+    MdArray,
+    MdComponent,
+    MountEvent,
+    MountWatcher,
+    read_mount_namespace,
+    read_mounts,
+};
 #[cfg(target_os = "macos")]
 pub use macos::read_mounts;
 #[cfg(windows)]
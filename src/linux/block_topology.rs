@@ -0,0 +1,762 @@
+use {
+    crate::*,
+    lazy_regex::*,
+    snafu::prelude::*,
+    std::{
+        collections::HashMap,
+        fs,
+        path::Path,
+        str::FromStr,
+    },
+};
+
+/// The kind of a block device, as deduced from /sys/block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockDeviceKind {
+    /// a whole disk (or what's seen as such, eg a virtual disk)
+    Disk,
+    Partition,
+    /// a device mapper device (LVM, dm-crypt, multipath, etc.)
+    DeviceMapper,
+    /// a software RAID array
+    Md,
+    Loop,
+    Bcache,
+    Zram,
+}
+
+/// A block device, with the devices it's built on (its parents,
+/// found in `slaves/`, or the disk of a partition) and the ones
+/// built on it (its children, found in `holders/`, or the partitions
+/// of a disk)
+#[derive(Debug, Clone)]
+pub struct BlockNode {
+    /// a name, like "sda", "sda1", "dm-0", "md127"
+    pub name: String,
+    pub id: DeviceId,
+    pub kind: BlockDeviceKind,
+    /// a name for a /dev/mapper/ device
+    pub dm_name: Option<String>,
+    /// the uuid of a device mapper device, starting with the
+    /// name of its subsystem (eg "LVM-", "CRYPT-")
+    pub dm_uuid: Option<String>,
+    /// the kind of a device mapper device (crypt, LVM, multipath, etc.)
+    pub dm_kind: Option<DmKind>,
+    /// whether a device mapper device is suspended, its I/O being queued
+    pub dm_suspended: Option<bool>,
+    /// the state of the underlying device, as given by its driver
+    /// (eg "running" or "offline" for a SCSI disk)
+    pub state: Option<String>,
+    /// the level of a software RAID array (eg "raid1", "linear")
+    pub raid_level: Option<String>,
+    /// whether the device is used as a cache for other devices
+    /// (bcache cache device, dm-cache or writecache volume)
+    pub cache: bool,
+    pub parents: Vec<DeviceId>,
+    pub children: Vec<DeviceId>,
+}
+
+/// The graph of the block devices of the system, as shown by `lsblk`
+#[derive(Debug, Clone)]
+pub struct BlockTopology {
+    nodes: Vec<BlockNode>,
+    index: HashMap<DeviceId, usize>,
+    btrfs: Vec<BtrfsFs>,
+}
+
+/// A btrfs filesystem spanning several devices, as found in /sys/fs/btrfs
+#[derive(Debug, Clone)]
+struct BtrfsFs {
+    devices: Vec<DeviceId>,
+    /// the role of the devices, deduced from the profile of the data
+    role: Option<DiskRole>,
+}
+
+impl BlockTopology {
+    /// Read the block devices in /sys/block (under the system root
+    /// of the options, if any)
+    pub fn read(options: &ReadOptions) -> Result<Self, Error> {
+        let sys_block = options.system_path("/sys/block");
+        let entries = fs::read_dir(&sys_block).context(CantReadDirSnafu { path: &sys_block })?;
+        let mut nodes = Vec::new();
+        // names of the parents of each node, by node index
+        let mut parent_names: Vec<Vec<String>> = Vec::new();
+        for e in entries.flatten() {
+            let dir = e.path();
+            let name = e.file_name().to_string_lossy().to_string();
+            let Some(id) = read_device_id(&dir) else {
+                continue;
+            };
+            let dm_name = read_trimmed(&dir.join("dm/name"));
+            let dm_uuid = read_trimmed(&dir.join("dm/uuid"));
+            let cache = is_cache(&dir, dm_name.as_deref());
+            nodes.push(BlockNode {
+                kind: device_kind(&name, &dir),
+                dm_name,
+                dm_kind: dm_uuid.as_deref().map(DmKind::from_uuid),
+                dm_uuid,
+                dm_suspended: sys::read_file_as_bool(dir.join("dm/suspended")),
+                state: read_trimmed(&dir.join("device/state")),
+                raid_level: read_trimmed(&dir.join("md/level")),
+                cache,
+                name: name.clone(),
+                id,
+                parents: Vec::new(),
+                children: Vec::new(),
+            });
+            let mut parents = dir_names(&dir.join("slaves"));
+            // the cache devices of a bcache device aren't in its slaves
+            parents.extend(bcache_cache_names(&dir));
+            parent_names.push(parents);
+            // partitions are in sub directories (other ones, like
+            // `device/` of a NVMe disk, may also have a device id)
+            for sub in fs::read_dir(&dir).into_iter().flatten().flatten() {
+                if !sub.path().join("partition").exists() {
+                    continue;
+                }
+                let Some(sub_id) = read_device_id(&sub.path()) else {
+                    continue;
+                };
+                nodes.push(BlockNode {
+                    name: sub.file_name().to_string_lossy().to_string(),
+                    id: sub_id,
+                    kind: BlockDeviceKind::Partition,
+                    dm_name: None,
+                    dm_uuid: None,
+                    dm_kind: None,
+                    dm_suspended: None,
+                    state: None,
+                    raid_level: None,
+                    cache: is_cache(&sub.path(), None),
+                    parents: Vec::new(),
+                    children: Vec::new(),
+                });
+                parent_names.push(vec![name.clone()]);
+            }
+        }
+        let btrfs = read_btrfs_filesystems(&options.system_path("/sys/fs/btrfs"));
+        Ok(Self::new(nodes, parent_names, btrfs))
+    }
+    fn new(
+        nodes: Vec<BlockNode>,
+        parent_names: Vec<Vec<String>>,
+        btrfs: Vec<(Vec<String>, Option<DiskRole>)>,
+    ) -> Self {
+        let mut pairs: Vec<(BlockNode, Vec<String>)> =
+            nodes.into_iter().zip(parent_names).collect();
+        pairs.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        let (mut nodes, parent_names): (Vec<BlockNode>, Vec<Vec<String>>) =
+            pairs.into_iter().unzip();
+        let by_name: HashMap<String, DeviceId> =
+            nodes.iter().map(|n| (n.name.clone(), n.id)).collect();
+        let mut edges = Vec::new(); // (parent, child)
+        for (node, names) in nodes.iter().zip(parent_names) {
+            for name in names {
+                if let Some(&parent) = by_name.get(&name) {
+                    edges.push((parent, node.id));
+                }
+            }
+        }
+        let index: HashMap<DeviceId, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, n)| (n.id, idx))
+            .collect();
+        for (parent, child) in edges {
+            nodes[index[&child]].parents.push(parent);
+            nodes[index[&parent]].children.push(child);
+        }
+        // the target of a LVM volume is known from its hidden volumes
+        for idx in 0..nodes.len() {
+            if nodes[idx].dm_kind != Some(DmKind::Lvm) {
+                continue;
+            }
+            let kind = DmKind::of_lvm_volume(
+                nodes[idx]
+                    .parents
+                    .iter()
+                    .filter_map(|parent| nodes[index[parent]].dm_name.as_deref()),
+            );
+            nodes[idx].dm_kind = Some(kind);
+        }
+        let btrfs = btrfs
+            .into_iter()
+            .map(|(names, role)| BtrfsFs {
+                devices: names
+                    .iter()
+                    .filter_map(|name| by_name.get(name).copied())
+                    .collect(),
+                role,
+            })
+            .filter(|fs| fs.devices.len() > 1)
+            .collect();
+        Self {
+            nodes,
+            index,
+            btrfs,
+        }
+    }
+    /// Return all block devices, sorted by name
+    pub fn nodes(&self) -> &[BlockNode] {
+        &self.nodes
+    }
+    pub fn get(
+        &self,
+        id: DeviceId,
+    ) -> Option<&BlockNode> {
+        self.index.get(&id).map(|&idx| &self.nodes[idx])
+    }
+    pub fn get_by_name(
+        &self,
+        name: &str,
+    ) -> Option<&BlockNode> {
+        self.nodes.iter().find(|n| n.name == name)
+    }
+    pub fn get_by_dm_name(
+        &self,
+        dm_name: &str,
+    ) -> Option<&BlockNode> {
+        self.nodes
+            .iter()
+            .find(|n| n.dm_name.as_deref() == Some(dm_name))
+    }
+    /// Return the block device of a mount, found by device id, or
+    /// by the name of the mounted device when the device id is
+    /// virtual (eg on btrfs)
+    pub fn get_for_mount(
+        &self,
+        info: &MountInfo,
+    ) -> Option<&BlockNode> {
+        self.get(info.dev)
+            .or_else(|| info.dm_name().and_then(|name| self.get_by_dm_name(name)))
+            .or_else(|| info.fs_name().and_then(|name| self.get_by_name(name)))
+    }
+    /// Return the devices the given device is directly built on
+    pub fn parents(
+        &self,
+        id: DeviceId,
+    ) -> impl Iterator<Item = &BlockNode> {
+        self.get(id)
+            .map(|n| n.parents.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|&id| self.get(id))
+    }
+    /// Return the devices directly built on the given device
+    pub fn children(
+        &self,
+        id: DeviceId,
+    ) -> impl Iterator<Item = &BlockNode> {
+        self.get(id)
+            .map(|n| n.children.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|&id| self.get(id))
+    }
+    /// Return the given device and all the devices it's built on,
+    /// directly or not
+    pub fn ancestors(
+        &self,
+        id: DeviceId,
+    ) -> Vec<&BlockNode> {
+        self.collect(id, |node| &node.parents)
+    }
+    /// Return the given device and all the devices built on it,
+    /// directly or not
+    pub fn descendants(
+        &self,
+        id: DeviceId,
+    ) -> Vec<&BlockNode> {
+        self.collect(id, |node| &node.children)
+    }
+    fn collect<F>(
+        &self,
+        id: DeviceId,
+        next: F,
+    ) -> Vec<&BlockNode>
+    where
+        F: Fn(&BlockNode) -> &Vec<DeviceId>,
+    {
+        self.walk(id, next)
+            .into_iter()
+            .map(|(node, _)| node)
+            .collect()
+    }
+    /// Return the given device and the devices reached from it with the
+    /// `next` function, in depth-first order, each one only once and with
+    /// the depth at which it was first reached (0 for the given device)
+    fn walk<F>(
+        &self,
+        id: DeviceId,
+        next: F,
+    ) -> Vec<(&BlockNode, usize)>
+    where
+        F: Fn(&BlockNode) -> &Vec<DeviceId>,
+    {
+        let mut found: Vec<(&BlockNode, usize)> = Vec::new();
+        let mut stack = vec![(id, 0)];
+        while let Some((id, depth)) = stack.pop() {
+            let Some(node) = self.get(id) else {
+                continue;
+            };
+            if found.iter().any(|(n, _)| n.id == id) {
+                continue; // already seen, which also protects from cycles
+            }
+            found.push((node, depth));
+            stack.extend(next(node).iter().rev().map(|&id| (id, depth + 1)));
+        }
+        found
+    }
+    /// Return the paths of a multipath device whose device isn't known
+    /// to be offline.
+    ///
+    /// A path failed by the checker of multipathd while its device is
+    /// still running is returned too, as the state of the paths in the
+    /// multipath target is only available through device mapper ioctls.
+    pub fn online_paths(
+        &self,
+        id: DeviceId,
+    ) -> Vec<&BlockNode> {
+        let Some(node) = self.get(id) else {
+            return Vec::new();
+        };
+        if node.dm_kind != Some(DmKind::Multipath) {
+            return Vec::new();
+        }
+        self.parents(id)
+            .filter(|path| matches!(path.state.as_deref(), None | Some("running" | "live")))
+            .collect()
+    }
+    /// Return the disks the given device is built on, that is the
+    /// devices without parent among its ancestors (the device itself
+    /// if it has no parent)
+    pub fn leaf_disks(
+        &self,
+        id: DeviceId,
+    ) -> Vec<&BlockNode> {
+        let mut disks: Vec<&BlockNode> = self
+            .ancestors(id)
+            .into_iter()
+            .filter(|n| n.parents.is_empty())
+            .collect();
+        disks.sort_by(|a, b| a.name.cmp(&b.name));
+        disks
+    }
+    /// Return the disks the filesystem of the mount is built on
+    pub fn leaf_disks_of_mount(
+        &self,
+        info: &MountInfo,
+    ) -> Vec<&BlockNode> {
+        self.backing_disks_of_mount(info)
+            .into_iter()
+            .map(|(node, _)| node)
+            .collect()
+    }
+    /// Return the disks the given device is built on, with their role,
+    /// sorted by name
+    pub fn backing_disks(
+        &self,
+        id: DeviceId,
+    ) -> Vec<(&BlockNode, DiskRole)> {
+        let mut found = Vec::new();
+        self.collect_backing(id, None, &mut found, 0);
+        finish_backing(found)
+    }
+    /// Return the disks the filesystem of the mount is built on, with
+    /// their role, including all the devices of a multi-device btrfs
+    pub fn backing_disks_of_mount(
+        &self,
+        info: &MountInfo,
+    ) -> Vec<(&BlockNode, DiskRole)> {
+        let role = self.btrfs_of_mount(info).and_then(|fs| fs.role);
+        let mut found = Vec::new();
+        for node in self.mount_devices(info) {
+            self.collect_backing(node.id, role, &mut found, 0);
+        }
+        finish_backing(found)
+    }
+    /// Return the devices the filesystem of the mount is directly on: all
+    /// the devices of a multi-device btrfs, or the device of the mount
+    pub fn mount_devices(
+        &self,
+        info: &MountInfo,
+    ) -> Vec<&BlockNode> {
+        match self.btrfs_of_mount(info) {
+            Some(fs) => fs.devices.iter().filter_map(|&id| self.get(id)).collect(),
+            None => self.get_for_mount(info).into_iter().collect(),
+        }
+    }
+    fn btrfs_of_mount(
+        &self,
+        info: &MountInfo,
+    ) -> Option<&BtrfsFs> {
+        if info.fs_type != "btrfs" {
+            return None;
+        }
+        let node = self.get_for_mount(info)?;
+        self.btrfs.iter().find(|fs| fs.devices.contains(&node.id))
+    }
+    /// Return the given device and the devices it's built on, from the
+    /// top, in depth-first order, with their depth (0 for the given device)
+    pub fn layers(
+        &self,
+        id: DeviceId,
+    ) -> Vec<(&BlockNode, usize)> {
+        self.walk(id, |node| &node.parents)
+    }
+    /// Walk down from the given device to the disks it's built on, the
+    /// role of a disk being given by the nearest device above it which
+    /// tells it, a physical volume being less specific than other roles
+    fn collect_backing<'t>(
+        &'t self,
+        id: DeviceId,
+        role: Option<DiskRole>,
+        found: &mut Vec<(&'t BlockNode, Option<DiskRole>)>,
+        depth: usize,
+    ) {
+        let Some(node) = self.get(id) else {
+            return;
+        };
+        if depth > self.nodes.len() {
+            return; // there's a cycle
+        }
+        if node.parents.is_empty() {
+            if !found.iter().any(|(n, _)| n.id == id) {
+                found.push((node, role));
+            }
+            return;
+        }
+        for parent in self.parents(id) {
+            let role = match (role, self.parent_role(node, parent)) {
+                (Some(role), Some(DiskRole::PhysicalVolume)) => Some(role),
+                (_, Some(parent_role)) => Some(parent_role),
+                (role, None) => role,
+            };
+            self.collect_backing(parent.id, role, found, depth + 1);
+        }
+    }
+    /// Return the role of a device in the device built on it, if it's known
+    fn parent_role(
+        &self,
+        node: &BlockNode,
+        parent: &BlockNode,
+    ) -> Option<DiskRole> {
+        if parent.cache {
+            return Some(DiskRole::Cache);
+        }
+        if self.parents(node.id).any(|p| p.cache) {
+            return Some(DiskRole::Cached);
+        }
+        let is_lvm = |n: &BlockNode| n.dm_uuid.as_ref().is_some_and(|u| u.starts_with("LVM-"));
+        match node.kind {
+            BlockDeviceKind::Md => match node.raid_level.as_deref()? {
+                "raid1" | "raid10" => Some(DiskRole::Mirror),
+                "raid0" => Some(DiskRole::Stripe),
+                "raid4" | "raid5" | "raid6" => Some(DiskRole::Parity),
+                "linear" => Some(DiskRole::Span),
+                _ => Some(DiskRole::Member),
+            },
+            BlockDeviceKind::DeviceMapper if is_lvm(node) && !is_lvm(parent) => {
+                Some(DiskRole::PhysicalVolume)
+            }
+            _ => None,
+        }
+    }
+    /// Return the mounts one of whose devices (see [BlockTopology::mount_devices])
+    /// is the given one or is built on it
+    pub fn mounts_on<'m>(
+        &self,
+        id: DeviceId,
+        mounts: &'m [Mount],
+    ) -> Vec<&'m Mount> {
+        let descendants = self.descendants(id);
+        mounts
+            .iter()
+            .filter(|m| {
+                self.mount_devices(&m.info)
+                    .iter()
+                    .any(|node| descendants.iter().any(|d| d.id == node.id))
+            })
+            .collect()
+    }
+}
+
+impl BlockNode {
+    /// Return the kind of layer this device is in a storage stack
+    pub fn storage_layer_kind(&self) -> StorageLayerKind {
+        match self.kind {
+            BlockDeviceKind::Disk => StorageLayerKind::Disk,
+            BlockDeviceKind::Partition => StorageLayerKind::Partition,
+            BlockDeviceKind::DeviceMapper => match self.dm_kind {
+                Some(DmKind::Crypt) => StorageLayerKind::Crypt,
+                Some(DmKind::Lvm | DmKind::Raid | DmKind::Vdo) => StorageLayerKind::LogicalVolume,
+                Some(DmKind::Cache | DmKind::Writecache) => StorageLayerKind::Cache,
+                _ => StorageLayerKind::DeviceMapper,
+            },
+            BlockDeviceKind::Md => StorageLayerKind::Raid,
+            BlockDeviceKind::Loop => StorageLayerKind::Loop,
+            BlockDeviceKind::Bcache => StorageLayerKind::Cache,
+            BlockDeviceKind::Zram => StorageLayerKind::Ram,
+        }
+    }
+}
+
+/// Give a role to the disks which have none, and sort them by name
+fn finish_backing(found: Vec<(&BlockNode, Option<DiskRole>)>) -> Vec<(&BlockNode, DiskRole)> {
+    let default_role = if found.len() == 1 {
+        DiskRole::Single
+    } else {
+        DiskRole::Member
+    };
+    let mut disks: Vec<(&BlockNode, DiskRole)> = found
+        .into_iter()
+        .map(|(node, role)| (node, role.unwrap_or(default_role)))
+        .collect();
+    disks.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+    disks
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    sys::read_file(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_device_id(dir: &Path) -> Option<DeviceId> {
+    sys::read_file(dir.join("dev"))
+        .ok()
+        .and_then(|s| DeviceId::from_str(s.trim()).ok())
+}
+
+/// return the names of the entries of the directory, if it exists
+fn dir_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect()
+}
+
+/// tell whether the device is a bcache cache device or a volume
+/// used by LVM as cache (a cache pool or a cachevol)
+fn is_cache(
+    dir: &Path,
+    dm_name: Option<&str>,
+) -> bool {
+    dir.join("bcache/set").exists()
+        || dm_name.is_some_and(|name| regex_is_match!(r"_c(data|meta|vol)(-c(data|meta))?$", name))
+}
+
+/// return the names of the cache devices of a bcache device, found
+/// in the cache set of its backing device
+fn bcache_cache_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir.join("bcache/cache"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| regex_is_match!(r"^cache\d+$", &e.file_name().to_string_lossy()))
+        .filter_map(|e| fs::canonicalize(e.path()).ok())
+        .filter_map(|path| {
+            path.parent()
+                .and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+/// Read the btrfs filesystems, as the names of their devices and
+/// the role deduced from the profile of their data
+fn read_btrfs_filesystems(sys_btrfs: &Path) -> Vec<(Vec<String>, Option<DiskRole>)> {
+    let mut filesystems = Vec::new();
+    for e in fs::read_dir(sys_btrfs).into_iter().flatten().flatten() {
+        let devices = dir_names(&e.path().join("devices"));
+        if devices.len() < 2 {
+            continue;
+        }
+        let profiles = dir_names(&e.path().join("allocation/data"));
+        // when there are several profiles (eg during a conversion), we
+        // prefer the one which isn't the default
+        let profile = profiles
+            .iter()
+            .find(|p| *p != "single")
+            .or_else(|| profiles.first());
+        let role = profile.and_then(|profile| match profile.as_str() {
+            "raid1" | "raid1c3" | "raid1c4" | "raid10" => Some(DiskRole::Mirror),
+            "raid0" => Some(DiskRole::Stripe),
+            "raid5" | "raid6" => Some(DiskRole::Parity),
+            "single" => Some(DiskRole::Span),
+            _ => None,
+        });
+        filesystems.push((devices, role));
+    }
+    filesystems
+}
+
+fn device_kind(
+    name: &str,
+    dir: &Path,
+) -> BlockDeviceKind {
+    if dir.join("dm").exists() {
+        BlockDeviceKind::DeviceMapper
+    } else if dir.join("md").exists() {
+        BlockDeviceKind::Md
+    } else if dir.join("loop").exists() || name.starts_with("loop") {
+        BlockDeviceKind::Loop
+    } else if name.starts_with("bcache") {
+        BlockDeviceKind::Bcache
+    } else if name.starts_with("zram") {
+        BlockDeviceKind::Zram
+    } else {
+        BlockDeviceKind::Disk
+    }
+}
+
+#[test]
+fn test_block_topology() {
+    let tree = super::fake_tree::FakeTree::new("topology");
+    let write = |path: &str, content: &str| tree.write(path, content);
+    write("sys/block/sda/dev", "8:0\n");
+    write("sys/block/sda/sda1/dev", "8:1\n");
+    write("sys/block/sda/sda1/partition", "1\n");
+    write("sys/block/sda/sda2/dev", "8:2\n");
+    write("sys/block/sda/sda2/partition", "2\n");
+    write("sys/block/sdb/dev", "8:16\n");
+    write("sys/block/sdb/sdb1/dev", "8:17\n");
+    write("sys/block/sdb/sdb1/partition", "1\n");
+    write("sys/block/sdc/dev", "8:32\n");
+    write("sys/block/sdc/sdc1/dev", "8:33\n");
+    write("sys/block/sdc/sdc1/partition", "1\n");
+    write("sys/block/md0/dev", "9:0\n");
+    write("sys/block/md0/md/level", "raid1\n");
+    write("sys/block/md0/slaves/sdb1", "");
+    write("sys/block/md0/slaves/sdc1", "");
+    write("sys/block/dm-0/dev", "253:0\n");
+    write("sys/block/dm-0/dm/name", "luks-home\n");
+    write("sys/block/dm-0/slaves/sda2", "");
+    write("sys/block/dm-1/dev", "253:1\n");
+    write("sys/block/dm-1/dm/name", "vg-home\n");
+    write("sys/block/dm-1/slaves/dm-0", "");
+    write("sys/block/dm-0/dm/uuid", "CRYPT-LUKS2-abc-luks-home\n");
+    write("sys/block/dm-1/dm/uuid", "LVM-abc\n");
+    // a bcache device, with a HDD cached by a SSD
+    write("sys/block/sdd/dev", "8:48\n");
+    write("sys/block/nvme0n1/dev", "259:0\n");
+    // the controller of a NVMe disk, which isn't a partition
+    write("sys/block/nvme0n1/device/dev", "241:0\n");
+    write("sys/block/nvme0n1/bcache/set/cache0", "");
+    write("sys/block/bcache0/dev", "252:0\n");
+    write("sys/block/bcache0/slaves/sdd", "");
+    tree.symlink(
+        tree.root().join("sys/block/nvme0n1/bcache"),
+        "sys/block/bcache0/bcache/cache/cache0",
+    );
+    // a multipath device, with one of its paths offline
+    write("sys/block/sdg/dev", "8:96\n");
+    write("sys/block/sdg/device/state", "running\n");
+    write("sys/block/sdh/dev", "8:112\n");
+    write("sys/block/sdh/device/state", "offline\n");
+    write("sys/block/dm-2/dev", "253:2\n");
+    write("sys/block/dm-2/dm/uuid", "mpath-3600508b4001\n");
+    write("sys/block/dm-2/dm/suspended", "0\n");
+    write("sys/block/dm-2/slaves/sdg", "");
+    write("sys/block/dm-2/slaves/sdh", "");
+    // a btrfs filesystem mirrored on two disks
+    write("sys/block/sde/dev", "8:64\n");
+    write("sys/block/sdf/dev", "8:80\n");
+    write("sys/fs/btrfs/1234/devices/sde", "");
+    write("sys/fs/btrfs/1234/devices/sdf", "");
+    tree.mkdir("sys/fs/btrfs/1234/allocation/data/raid1");
+    let options = ReadOptions::default().system_root(tree.root());
+    let topology = BlockTopology::read(&options).unwrap();
+    let dev = |s: &str| DeviceId::from_str(s).unwrap();
+    let names = |nodes: Vec<&BlockNode>| -> Vec<String> {
+        nodes.into_iter().map(|n| n.name.clone()).collect()
+    };
+    let roles = |disks: Vec<(&BlockNode, DiskRole)>| -> Vec<(String, DiskRole)> {
+        disks
+            .into_iter()
+            .map(|(n, role)| (n.name.clone(), role))
+            .collect()
+    };
+    assert_eq!(topology.nodes().len(), 18);
+    let mpath = topology.get(dev("253:2")).unwrap();
+    assert_eq!(mpath.dm_kind, Some(DmKind::Multipath));
+    assert_eq!(mpath.dm_suspended, Some(false));
+    assert_eq!(names(topology.online_paths(dev("253:2"))), vec!["sdg"]);
+    assert_eq!(
+        topology.get(dev("253:1")).unwrap().dm_kind,
+        Some(DmKind::Lvm)
+    );
+    assert_eq!(
+        topology.get(dev("253:0")).unwrap().kind,
+        BlockDeviceKind::DeviceMapper
+    );
+    assert_eq!(names(topology.leaf_disks(dev("253:1"))), vec!["sda"]);
+    assert_eq!(names(topology.leaf_disks(dev("9:0"))), vec!["sdb", "sdc"]);
+    let layers: Vec<(StorageLayerKind, usize)> = topology
+        .layers(dev("253:1"))
+        .into_iter()
+        .map(|(n, depth)| (n.storage_layer_kind(), depth))
+        .collect();
+    assert_eq!(
+        layers,
+        vec![
+            (StorageLayerKind::LogicalVolume, 0),
+            (StorageLayerKind::Crypt, 1),
+            (StorageLayerKind::Partition, 2),
+            (StorageLayerKind::Disk, 3),
+        ]
+    );
+    assert_eq!(
+        roles(topology.backing_disks(dev("9:0"))),
+        vec![
+            ("sdb".to_string(), DiskRole::Mirror),
+            ("sdc".to_string(), DiskRole::Mirror),
+        ]
+    );
+    assert_eq!(
+        roles(topology.backing_disks(dev("253:1"))),
+        vec![("sda".to_string(), DiskRole::PhysicalVolume)]
+    );
+    assert_eq!(
+        roles(topology.backing_disks(dev("252:0"))),
+        vec![
+            ("nvme0n1".to_string(), DiskRole::Cache),
+            ("sdd".to_string(), DiskRole::Cached),
+        ]
+    );
+    assert_eq!(
+        roles(topology.backing_disks(dev("8:1"))),
+        vec![("sda".to_string(), DiskRole::Single)]
+    );
+    assert_eq!(
+        names(topology.descendants(dev("8:0"))),
+        vec!["sda", "sda1", "sda2", "dm-0", "dm-1"]
+    );
+    let mount = Mount::from_mountinfo_line;
+    let mounts = vec![
+        mount("25 1 8:1 / / rw - ext4 /dev/sda1 rw"),
+        mount("40 25 253:1 / /home rw - ext4 /dev/mapper/vg-home rw"),
+        mount("41 25 9:0 / /data rw - ext4 /dev/md0 rw"),
+        mount("42 25 0:40 / /pool rw - btrfs /dev/sde rw"),
+    ];
+    assert_eq!(
+        roles(topology.backing_disks_of_mount(&mounts[3].info)),
+        vec![
+            ("sde".to_string(), DiskRole::Mirror),
+            ("sdf".to_string(), DiskRole::Mirror),
+        ]
+    );
+    let on_sda: Vec<MountId> = topology
+        .mounts_on(dev("8:0"), &mounts)
+        .iter()
+        .filter_map(|m| m.info.id)
+        .collect();
+    assert_eq!(on_sda, vec![25, 40]);
+    // a multi-device btrfs is on all its devices
+    let on_sdf: Vec<MountId> = topology
+        .mounts_on(dev("8:80"), &mounts)
+        .iter()
+        .filter_map(|m| m.info.id)
+        .collect();
+    assert_eq!(on_sdf, vec![42]);
+}
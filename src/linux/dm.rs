@@ -0,0 +1,110 @@
+use lazy_regex::*;
+
+/// The kind of a device mapper device, as deduced from its uuid
+/// (and, for LVM volumes, from the devices they're built on)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DmKind {
+    /// an encrypted device (LUKS or plain dm-crypt)
+    Crypt,
+    /// a LVM logical volume
+    Lvm,
+    /// a device whose parents are the paths to the same disk
+    Multipath,
+    /// a read-only device whose blocks are checked against a hash tree
+    Verity,
+    /// a device checking the integrity of its blocks
+    Integrity,
+    /// a RAID array (dm-raid, LVM raid, fake RAID)
+    Raid,
+    /// a device cached by a faster one (dm-cache)
+    Cache,
+    /// a device whose writes are cached by a faster one (dm-writecache)
+    Writecache,
+    /// a deduplicated and compressed device
+    Vdo,
+    /// a device of a stratis pool
+    Stratis,
+    /// a partition of a device mapper device (eg made by kpartx)
+    Part,
+    Other,
+}
+
+impl DmKind {
+    /// Deduce the kind of a device mapper device from its uuid, as
+    /// found in /sys/block/dm-N/dm/uuid
+    pub fn from_uuid(uuid: &str) -> Self {
+        if uuid.starts_with("CRYPT-VERITY-") {
+            Self::Verity
+        } else if uuid.starts_with("CRYPT-INTEGRITY-") || uuid.starts_with("CRYPT-SUBDEV-") {
+            Self::Integrity
+        } else if uuid.starts_with("CRYPT-") {
+            Self::Crypt
+        } else if uuid.starts_with("LVM-") {
+            Self::Lvm
+        } else if uuid.starts_with("mpath-") {
+            Self::Multipath
+        } else if regex_is_match!(r"^part\d+-", uuid) {
+            Self::Part
+        } else if uuid.starts_with("DMRAID-") {
+            Self::Raid
+        } else if uuid.starts_with("VDO-") {
+            Self::Vdo
+        } else if uuid.starts_with("stratis-") {
+            Self::Stratis
+        } else {
+            Self::Other
+        }
+    }
+    /// Refine the kind of a LVM volume from the hidden volumes it's
+    /// built on, given by their device mapper names (eg "vg-home_corig")
+    pub(crate) fn of_lvm_volume<'n, I>(parent_names: I) -> Self
+    where
+        I: IntoIterator<Item = &'n str>,
+    {
+        for name in parent_names {
+            if name.ends_with("_wcorig") {
+                return Self::Writecache;
+            }
+            if name.ends_with("_corig") {
+                return Self::Cache;
+            }
+            if regex_is_match!(r"_[rm]image_\d+$", name) {
+                return Self::Raid;
+            }
+            if name.ends_with("-vpool") {
+                return Self::Vdo;
+            }
+        }
+        Self::Lvm
+    }
+}
+
+#[test]
+fn test_dm_kind() {
+    let kinds = [
+        ("CRYPT-LUKS2-0123456789abcdef-luks-home", DmKind::Crypt),
+        ("CRYPT-PLAIN-swap", DmKind::Crypt),
+        ("CRYPT-VERITY-0123456789abcdef-root", DmKind::Verity),
+        ("CRYPT-INTEGRITY-data", DmKind::Integrity),
+        ("LVM-abcdefABCDEF", DmKind::Lvm),
+        ("mpath-3600508b400105e210000900000490000", DmKind::Multipath),
+        (
+            "part1-mpath-3600508b400105e210000900000490000",
+            DmKind::Part,
+        ),
+        ("stratis-1-0123-thin-fs-4567", DmKind::Stratis),
+        ("something-else", DmKind::Other),
+    ];
+    for (uuid, kind) in kinds {
+        assert_eq!(DmKind::from_uuid(uuid), kind, "uuid: {uuid}");
+    }
+    assert_eq!(
+        DmKind::of_lvm_volume(["vg-home_corig", "vg-cpool_cdata"]),
+        DmKind::Cache
+    );
+    assert_eq!(
+        DmKind::of_lvm_volume(["vg-data_rimage_0", "vg-data_rmeta_0"]),
+        DmKind::Raid
+    );
+    assert_eq!(DmKind::of_lvm_volume(["sda2"]), DmKind::Lvm);
+}
@@ -0,0 +1,61 @@
+use std::{
+    fs,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// A temporary directory standing for a system root, or for a device
+/// directory of /sys, in tests. It's removed when dropped, even when
+/// an assertion fails.
+pub(crate) struct FakeTree {
+    root: PathBuf,
+}
+
+impl FakeTree {
+    /// Create an empty tree, whose name must be unique among tests
+    pub(crate) fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("lfs-core-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+    /// Write a file, given by its path relative to the root,
+    /// creating the parent directories
+    pub(crate) fn write(
+        &self,
+        path: &str,
+        content: &str,
+    ) {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    /// Create a directory, and its parents
+    pub(crate) fn mkdir(
+        &self,
+        path: &str,
+    ) {
+        fs::create_dir_all(self.root.join(path)).unwrap();
+    }
+    /// Create a symbolic link, creating its parent directories
+    pub(crate) fn symlink<P: AsRef<Path>>(
+        &self,
+        target: P,
+        path: &str,
+    ) {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(target, path).unwrap();
+    }
+}
+
+impl Drop for FakeTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
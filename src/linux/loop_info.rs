@@ -0,0 +1,132 @@
+use {
+    crate::*,
+    std::{
+        ffi::OsStr,
+        fs,
+        os::unix::ffi::OsStrExt,
+        path::{
+            Path,
+            PathBuf,
+        },
+    },
+};
+
+/// the suffix the kernel adds to the backing file when it's deleted
+const DELETED_SUFFIX: &[u8] = b" (deleted)";
+
+/// Information on a loop device, as found in /sys/block/loopN/loop/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopInfo {
+    /// the file the loop device reads from (eg a snap, an ISO, a disk image)
+    pub backing_file: PathBuf,
+    /// whether the backing file has been deleted since the loop device setup
+    pub deleted: bool,
+    /// offset, in bytes, of the data in the backing file
+    pub offset: u64,
+    /// max size, in bytes, of the data in the backing file (0 when unlimited)
+    pub size_limit: u64,
+    /// whether the loop device is detached when its last user closes it
+    pub autoclear: bool,
+    pub read_only: bool,
+    /// id of the mount the backing file lives on, when found
+    pub backing_mount_id: Option<MountId>,
+    /// mount point of the mount the backing file lives on, when found
+    pub backing_mount_point: Option<PathBuf>,
+}
+
+impl LoopInfo {
+    /// Read the information of the loop device whose directory, in the
+    /// /sys/block tree, is given.
+    ///
+    /// Return None if it's not a loop device, or if it's not bound to a file.
+    pub fn read(sys_dir: &Path) -> Option<Self> {
+        let loop_dir = sys_dir.join("loop");
+        let mut backing_file = fs::read(loop_dir.join("backing_file")).ok()?;
+        while backing_file.last() == Some(&b'\n') {
+            backing_file.pop();
+        }
+        if backing_file.is_empty() {
+            return None;
+        }
+        let deleted = backing_file.ends_with(DELETED_SUFFIX);
+        if deleted {
+            backing_file.truncate(backing_file.len() - DELETED_SUFFIX.len());
+        }
+        let read_u64 = |name: &str| {
+            sys::read_file(loop_dir.join(name))
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or_default()
+        };
+        Some(Self {
+            backing_file: PathBuf::from(OsStr::from_bytes(&backing_file)),
+            deleted,
+            offset: read_u64("offset"),
+            size_limit: read_u64("sizelimit"),
+            autoclear: sys::read_file_as_bool(loop_dir.join("autoclear")).unwrap_or_default(),
+            read_only: sys::read_file_as_bool(sys_dir.join("ro")).unwrap_or_default(),
+            backing_mount_id: None,
+            backing_mount_point: None,
+        })
+    }
+}
+
+/// Set the mount of the backing files of the loop devices of the mounts,
+/// the device id of a file, when given, helping choosing among the
+/// candidate mounts
+pub(crate) fn resolve_loop_backing_files<F>(
+    mounts: &mut [Mount],
+    dev_of: F,
+) where
+    F: Fn(&Path) -> Option<DeviceId>,
+{
+    let resolver = MountResolver::new(mounts);
+    let mut resolved = Vec::new();
+    for (idx, mount) in mounts.iter().enumerate() {
+        let Some(loop_info) = mount.disk.as_ref().and_then(|d| d.loop_info.as_ref()) else {
+            continue;
+        };
+        if loop_info.deleted {
+            continue;
+        }
+        let path = &loop_info.backing_file;
+        if let Some(backing_mount) = resolver.find(path, dev_of(path)) {
+            resolved.push((
+                idx,
+                backing_mount.info.id,
+                backing_mount.info.mount_point.clone(),
+            ));
+        }
+    }
+    for (idx, mount_id, mount_point) in resolved {
+        if let Some(loop_info) = mounts[idx].disk.as_mut().and_then(|d| d.loop_info.as_mut()) {
+            loop_info.backing_mount_id = mount_id;
+            loop_info.backing_mount_point = Some(mount_point);
+        }
+    }
+}
+
+#[test]
+fn test_read_loop_info() {
+    let tree = super::fake_tree::FakeTree::new("loop");
+    let sys_dir = tree.root();
+    assert_eq!(LoopInfo::read(sys_dir), None);
+    tree.write(
+        "loop/backing_file",
+        "/var/lib/snapd/snaps/core_123.snap (deleted)\n",
+    );
+    tree.write("loop/offset", "1024\n");
+    tree.write("loop/sizelimit", "0\n");
+    tree.write("loop/autoclear", "1\n");
+    tree.write("ro", "1\n");
+    let loop_info = LoopInfo::read(sys_dir).unwrap();
+    assert_eq!(
+        loop_info.backing_file,
+        Path::new("/var/lib/snapd/snaps/core_123.snap")
+    );
+    assert!(loop_info.deleted);
+    assert_eq!(loop_info.offset, 1024);
+    assert_eq!(loop_info.size_limit, 0);
+    assert!(loop_info.autoclear);
+    assert!(loop_info.read_only);
+}
@@ -0,0 +1,164 @@
+use {
+    crate::*,
+    std::{
+        fs,
+        path::Path,
+    },
+};
+
+/// A device of a software RAID array, as found in /sys/block/mdX/md/dev-*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdComponent {
+    /// a name, like "sdb1"
+    pub name: String,
+    /// the position in the array, None for a spare
+    pub slot: Option<u32>,
+    /// the states of the device (eg "in_sync", "faulty", "spare", "write_mostly")
+    pub state: Vec<String>,
+}
+
+/// A software RAID array, as found in /sys/block/mdX/md/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdArray {
+    /// the RAID level, eg "raid1", "raid5", "linear"
+    pub level: String,
+    /// the number of devices of the array when complete
+    pub raid_disks: Option<u32>,
+    /// the number of missing or failed devices
+    pub degraded: Option<u32>,
+    /// the running operation, eg "idle", "resync", "recover", "check"
+    pub sync_action: Option<String>,
+    /// the progress of the running operation, as done and total sectors
+    pub sync_completed: Option<(u64, u64)>,
+    /// the state of the array, eg "clean", "active", "inactive", "readonly"
+    pub array_state: Option<String>,
+    /// the devices of the array, spares included, sorted by name
+    pub components: Vec<MdComponent>,
+}
+
+impl MdComponent {
+    /// tell whether the device has the given state (eg "in_sync")
+    pub fn has_state(
+        &self,
+        state: &str,
+    ) -> bool {
+        self.state.iter().any(|s| s == state)
+    }
+    /// tell whether the device failed and is no longer used
+    pub fn is_faulty(&self) -> bool {
+        self.has_state("faulty")
+    }
+    /// tell whether the device is a spare, used only to replace a
+    /// failed one (or being rebuilt to do so)
+    pub fn is_spare(&self) -> bool {
+        self.has_state("spare")
+    }
+}
+
+impl MdArray {
+    /// Read the information of the array whose directory, in the
+    /// /sys/block tree, is given.
+    ///
+    /// Return None if it's not a software RAID array.
+    pub fn read(sys_dir: &Path) -> Option<Self> {
+        let md_dir = sys_dir.join("md");
+        let read = |name: &str| {
+            sys::read_file(md_dir.join(name))
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let level = read("level")?;
+        let read_u32 = |name: &str| read(name).and_then(|s| s.parse().ok());
+        let sync_completed = read("sync_completed").and_then(|s| {
+            let (done, total) = s.split_once('/')?;
+            Some((done.trim().parse().ok()?, total.trim().parse().ok()?))
+        });
+        let mut components: Vec<MdComponent> = fs::read_dir(&md_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let file_name = e.file_name().to_string_lossy().to_string();
+                let name = file_name.strip_prefix("dev-")?.to_string();
+                let dev_dir = e.path();
+                let read = |file: &str| {
+                    sys::read_file(dev_dir.join(file))
+                        .ok()
+                        .map(|s| s.trim().to_string())
+                };
+                Some(MdComponent {
+                    name,
+                    slot: read("slot").and_then(|s| s.parse().ok()),
+                    state: read("state")
+                        .map(|s| s.split(',').map(|s| s.to_string()).collect())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+        components.sort_by(|a, b| a.name.cmp(&b.name));
+        Some(Self {
+            level,
+            raid_disks: read_u32("raid_disks"),
+            degraded: read_u32("degraded"),
+            sync_action: read("sync_action"),
+            sync_completed,
+            array_state: read("array_state"),
+            components,
+        })
+    }
+    /// Tell whether some devices of the array are missing or failed
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.is_some_and(|degraded| degraded > 0)
+            || self.components.iter().any(|c| c.is_faulty())
+    }
+    /// Return the progress, between 0 and 1, of the running
+    /// operation (resync, recovery, check, etc.), if any
+    pub fn sync_progress(&self) -> Option<f64> {
+        let (done, total) = self.sync_completed?;
+        if total == 0 {
+            return None;
+        }
+        Some(done as f64 / total as f64)
+    }
+}
+
+impl Mount {
+    /// Return the software RAID arrays the filesystem is stored on
+    pub fn md_arrays(&self) -> impl Iterator<Item = &MdArray> {
+        self.layers
+            .iter()
+            .filter_map(|layer| layer.md_array.as_ref())
+    }
+    /// Tell whether the filesystem is stored on a degraded RAID array
+    pub fn is_degraded(&self) -> bool {
+        self.md_arrays().any(|md| md.is_degraded())
+    }
+}
+
+#[test]
+fn test_read_md_array() {
+    let tree = super::fake_tree::FakeTree::new("md");
+    let sys_dir = tree.root();
+    assert_eq!(MdArray::read(sys_dir), None);
+    tree.write("md/level", "raid1\n");
+    tree.write("md/raid_disks", "2\n");
+    tree.write("md/degraded", "1\n");
+    tree.write("md/sync_action", "recover\n");
+    tree.write("md/sync_completed", "1024 / 4096\n");
+    tree.write("md/array_state", "clean\n");
+    tree.write("md/dev-sdb1/state", "in_sync\n");
+    tree.write("md/dev-sdb1/slot", "0\n");
+    tree.write("md/dev-sdc1/state", "spare\n");
+    tree.write("md/dev-sdc1/slot", "none\n");
+    let md = MdArray::read(sys_dir).unwrap();
+    assert_eq!(md.level, "raid1");
+    assert_eq!(md.raid_disks, Some(2));
+    assert_eq!(md.sync_completed, Some((1024, 4096)));
+    assert_eq!(md.sync_progress(), Some(0.25));
+    assert!(md.is_degraded());
+    assert_eq!(md.components.len(), 2);
+    assert_eq!(md.components[0].slot, Some(0));
+    assert!(md.components[1].is_spare());
+    assert_eq!(md.components[1].slot, None);
+}
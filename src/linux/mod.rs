@@ -1,69 +1,119 @@
-mod block_device;
+mod block_topology;
+mod dm;
+mod loop_info;
+mod md_array;
 mod read_mountinfos;
+mod read_proc_mounts;
+mod statmount;
+mod watcher;
+
+#[cfg(test)]
+mod fake_tree;
 
 use {
     crate::*,
-    block_device::*,
     lazy_regex::*,
+    snafu::prelude::*,
     std::{
+        collections::{
+            HashMap,
+            HashSet,
+        },
         ffi::CString,
+        fs,
         mem,
-        os::unix::ffi::OsStrExt,
-        path::Path,
+        os::unix::{
+            ffi::OsStrExt,
+            fs::MetadataExt,
+        },
+        path::{
+            Path,
+            PathBuf,
+        },
         sync::mpsc,
         thread,
         time::Duration,
     },
 };
 
-pub use read_mountinfos::ParseMountInfoError;
+pub use {
+    block_topology::*,
+    dm::*,
+    loop_info::*,
+    md_array::*,
+    read_mountinfos::ParseMountInfoError,
+    watcher::*,
+};
 
-pub fn new_disk(name: String) -> Disk {
-    let rotational = sys::read_file_as_bool(format!("/sys/block/{name}/queue/rotational"));
-    let removable = sys::read_file_as_bool(format!("/sys/block/{name}/removable"));
+pub fn new_disk(
+    name: String,
+    options: &ReadOptions,
+) -> Disk {
+    let sys_dir = options.system_path("/sys/block").join(&name);
+    let rotational = sys::read_file_as_bool(sys_dir.join("queue/rotational"));
+    let removable = sys::read_file_as_bool(sys_dir.join("removable"));
     let ram = regex_is_match!(r#"^zram\d*$"#, &name);
-    let dm_uuid = sys::read_file(format!("/sys/block/{name}/dm/uuid")).ok();
-    let crypted = dm_uuid
-        .as_ref()
-        .is_some_and(|uuid| uuid.starts_with("CRYPT-"));
-    let lvm = dm_uuid.is_some_and(|uuid| uuid.starts_with("LVM-"));
+    let dm_kind = sys::read_file(sys_dir.join("dm/uuid"))
+        .ok()
+        .map(|uuid| DmKind::from_uuid(uuid.trim()));
+    let crypted = dm_kind == Some(DmKind::Crypt);
+    let lvm = dm_kind == Some(DmKind::Lvm);
+    let loop_info = LoopInfo::read(&sys_dir);
     Disk {
         name,
         rotational,
         removable,
         image: false,
-        read_only: None,
+        read_only: loop_info.as_ref().map(|info| info.read_only),
         ram,
         lvm,
         crypted,
+        loop_info,
     }
 }
 
 /// Read all the mount points and load basic information on them
 pub fn read_mounts(options: &ReadOptions) -> Result<Vec<Mount>, Error> {
-    let by_label = read_by("label").ok();
-    let by_uuid = read_by("uuid").ok();
-    let by_partuuid = read_by("partuuid").ok();
-
-    // we'll find the disk for a filesystem by taking the longest
-    // disk whose name starts the one of our partition
-    // hence the sorting.
-    let bd_list = BlockDeviceList::read()?;
-    read_mountinfos::read_all_mountinfos()?
+    let dev_disk = options.system_path("/dev/disk");
+    let by_label = read_by_in(&dev_disk, "label").ok();
+    let by_uuid = read_by_in(&dev_disk, "uuid").ok();
+    let by_partuuid = read_by_in(&dev_disk, "partuuid").ok();
+
+    // the disk of a filesystem is found by climbing from its
+    // partition, if any, in the block devices graph
+    let topology = BlockTopology::read(options)?;
+    let nodev_fs_types = read_nodev_fs_types(options);
+    // a disk may be below many mounts and layers, so it's read only once
+    let mut disks = HashMap::new();
+    let mut mounts = read_mountinfos::read_all_mountinfos(options)?
         .drain(..)
-        .map(|info| {
-            let top_bd = bd_list.find_top(info.dev, info.dm_name(), info.fs_name());
-            let fs_label = get_label(&info.fs, by_label.as_deref());
+        .map(|mut info| {
+            info.nodev = nodev_fs_types
+                .as_ref()
+                .map(|types| types.contains(info.fs_base_type()));
+            let fs_label = get_label_os(&info.fs, by_label.as_deref());
             let uuid = get_label(&info.fs, by_uuid.as_deref());
             let part_uuid = get_label(&info.fs, by_partuuid.as_deref());
-            let disk = top_bd.map(|bd| new_disk(bd.name.clone()));
-            let stats = if info.is_remote() && !options.remote_stats {
+            let backing_disks: Vec<BackingDisk> = topology
+                .backing_disks_of_mount(&info)
+                .into_iter()
+                .map(|(node, role)| BackingDisk {
+                    disk: cached_disk(&mut disks, node, options),
+                    role,
+                })
+                .collect();
+            let layers = read_storage_layers(&topology, &info, &mut disks, options);
+            let disk = mount_disk(&backing_disks, &layers);
+            let stats = if info.shadowed {
+                Err(StatsError::Shadowed)
+            } else if info.is_remote() && !options.remote_stats {
                 Err(StatsError::Excluded)
             } else if let Some(timeout) = options.stats_timeout {
-                read_stats_with_timeout(&info.mount_point, timeout)
+                read_stats_with_timeout(&stats_path(options, &info.mount_point), timeout)
             } else {
-                read_stats(&info.mount_point)
+                read_stats(&stats_path(options, &info.mount_point))
             };
+            let stacked = StackedSource::from_mount_info(&info);
             Ok(Mount {
                 info,
                 fs_label,
@@ -71,9 +121,163 @@ pub fn read_mounts(options: &ReadOptions) -> Result<Vec<Mount>, Error> {
                 stats,
                 uuid,
                 part_uuid,
+                stacked,
+                backing_disks,
+                layers,
             })
         })
-        .collect()
+        .collect::<Result<Vec<Mount>, Error>>()?;
+    // device ids of the layers and loop backing files help choosing
+    // their mounts
+    let dev_of = |path: &Path| read_device_id(&stats_path(options, path), options);
+    resolve_stacked_layers(&mut mounts, dev_of);
+    resolve_loop_backing_files(&mut mounts, dev_of);
+    Ok(mounts)
+}
+
+/// Return the disk of a block device, reading it only if it's not
+/// already in the cache
+fn cached_disk(
+    disks: &mut HashMap<DeviceId, Disk>,
+    node: &BlockNode,
+    options: &ReadOptions,
+) -> Disk {
+    disks
+        .entry(node.id)
+        .or_insert_with(|| new_disk(node.name.clone(), options))
+        .clone()
+}
+
+/// Build the disk of a mount from the first of its backing disks (sorted
+/// by name), flagged crypted or lvm when the storage stack has such a layer
+fn mount_disk(
+    backing_disks: &[BackingDisk],
+    layers: &[StorageLayer],
+) -> Option<Disk> {
+    let disk = &backing_disks.first()?.disk;
+    let has_layer = |kind| layers.iter().any(|layer| layer.kind == kind);
+    Some(Disk {
+        crypted: disk.crypted || has_layer(StorageLayerKind::Crypt),
+        lvm: disk.lvm || has_layer(StorageLayerKind::LogicalVolume),
+        ..disk.clone()
+    })
+}
+
+/// Build the storage stack of a mount, from its filesystem down to
+/// its disks, or an empty stack if it's not on a block device
+fn read_storage_layers(
+    topology: &BlockTopology,
+    info: &MountInfo,
+    disks: &mut HashMap<DeviceId, Disk>,
+    options: &ReadOptions,
+) -> Vec<StorageLayer> {
+    let devices = topology.mount_devices(info);
+    if devices.is_empty() {
+        return Vec::new();
+    }
+    let mut layers = vec![StorageLayer {
+        kind: StorageLayerKind::Filesystem,
+        name: info.fs_type.clone(),
+        dm_name: None,
+        depth: 0,
+        disk: None,
+        dm_kind: None,
+        dm_suspended: None,
+        md_array: None,
+    }];
+    let sys_block = options.system_path("/sys/block");
+    for device in devices {
+        for (node, depth) in topology.layers(device.id) {
+            if layers.iter().skip(1).any(|layer| layer.name == node.name) {
+                continue; // shared by several devices of the filesystem
+            }
+            layers.push(StorageLayer {
+                kind: node.storage_layer_kind(),
+                name: node.name.clone(),
+                dm_name: node.dm_name.clone(),
+                dm_kind: node.dm_kind,
+                dm_suspended: node.dm_suspended,
+                depth: depth + 1,
+                disk: node
+                    .parents
+                    .is_empty()
+                    .then(|| cached_disk(disks, node, options)),
+                md_array: match node.kind {
+                    BlockDeviceKind::Md => MdArray::read(&sys_block.join(&node.name)),
+                    _ => None,
+                },
+            });
+        }
+    }
+    layers
+}
+
+/// Read the types of filesystems which need no block device, as
+/// flagged `nodev` in /proc/filesystems
+fn read_nodev_fs_types(options: &ReadOptions) -> Option<HashSet<String>> {
+    let content = sys::read_file(options.system_path("/proc/filesystems")).ok()?;
+    Some(
+        content
+            .lines()
+            .filter_map(|line| line.strip_prefix("nodev"))
+            .map(|fs_type| fs_type.trim().to_string())
+            .collect(),
+    )
+}
+
+/// Return the path of a file in the /proc/<pid> directory of the
+/// process of the options (by default /proc/self)
+pub(crate) fn proc_path(
+    options: &ReadOptions,
+    file: &str,
+) -> PathBuf {
+    match options.pid {
+        Some(pid) => options.system_path(format!("/proc/{pid}/{file}")),
+        None => options.system_path(format!("/proc/self/{file}")),
+    }
+}
+
+/// Return the path to stat to get the stats of a mount point,
+/// which, for another process, must go through its root
+pub(crate) fn stats_path(
+    options: &ReadOptions,
+    mount_point: &Path,
+) -> PathBuf {
+    match options.pid {
+        Some(_) => {
+            let relative = mount_point.strip_prefix("/").unwrap_or(mount_point);
+            proc_path(options, "root").join(relative)
+        }
+        None => options.system_path(mount_point),
+    }
+}
+
+/// Read the inode of the mount namespace of the process of the options
+/// (by default the current one), as found in `/proc/<pid>/ns/mnt`.
+///
+/// Processes sharing a mount namespace see the same mounts, so this
+/// can be used to avoid reading the same mount table several times.
+pub fn read_mount_namespace(options: &ReadOptions) -> Result<u64, Error> {
+    let path = proc_path(options, "ns/mnt");
+    let md = fs::metadata(&path).context(CantReadFileMetadataSnafu { path })?;
+    Ok(md.ino())
+}
+
+/// Read the device id of a path, with the stats timeout of the options,
+/// if any, as the path may be on an unreachable remote filesystem
+pub(crate) fn read_device_id(
+    path: &Path,
+    options: &ReadOptions,
+) -> Option<DeviceId> {
+    let Some(timeout) = options.stats_timeout else {
+        return DeviceId::of_path(path).ok();
+    };
+    let path = path.to_path_buf();
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(DeviceId::of_path(&path).ok());
+    });
+    rx.recv_timeout(timeout).ok().flatten()
 }
 
 pub fn read_stats_with_timeout(
@@ -135,3 +339,129 @@ pub fn read_stats(mount_point: &Path) -> Result<Stats, StatsError> {
         }
     }
 }
+
+/// Read the mounts of a fake system root, with a root and a data
+/// partition, a pseudo filesystem, a zfs pool and a snap
+#[cfg(test)]
+fn read_fake_system_mounts(name: &str) -> Vec<Mount> {
+    let tree = fake_tree::FakeTree::new(name);
+    tree.write(
+        "proc/self/mountinfo",
+        "25 1 8:1 / / rw,relatime - ext4 /dev/sda1 rw\n\
+         30 25 0:20 / /sys rw - sysfs sysfs rw\n\
+         40 25 0:45 / /tank rw - zfs tank rw\n\
+         41 25 8:2 / /data rw - ext4 /dev/sda2 rw\n\
+         42 25 7:0 / /snap/core ro - squashfs /dev/loop0 ro\n",
+    );
+    tree.write("proc/filesystems", "nodev\tsysfs\n\text4\nnodev\tzfs\n");
+    tree.write("sys/block/sda/dev", "8:0\n");
+    tree.write("sys/block/sda/queue/rotational", "0\n");
+    tree.write("sys/block/sda/removable", "0\n");
+    tree.write("sys/block/sda/sda1/dev", "8:1\n");
+    tree.write("sys/block/sda/sda1/partition", "1\n");
+    tree.write("sys/block/sda/sda2/dev", "8:2\n");
+    tree.write("sys/block/sda/sda2/partition", "2\n");
+    tree.write("sys/block/loop0/dev", "7:0\n");
+    tree.write("sys/block/loop0/ro", "1\n");
+    tree.write(
+        "sys/block/loop0/loop/backing_file",
+        "/var/lib/snapd/snaps/core.snap\n",
+    );
+    tree.symlink("../../sda1", "dev/disk/by-label/DATA");
+    // a label in latin-1, escaped by udev
+    tree.symlink("../../sda2", "dev/disk/by-label/caf\\xe9");
+    let options = ReadOptions::default()
+        .system_root(tree.root())
+        .stats_timeout(None);
+    let mounts = read_mounts(&options).unwrap();
+    assert_eq!(mounts.len(), 5);
+    mounts
+}
+
+#[test]
+fn test_read_mounts_in_system_root() {
+    let mounts = read_fake_system_mounts("root");
+    let disk = mounts[0].disk.as_ref().unwrap();
+    assert_eq!(disk.name, "sda");
+    assert_eq!(disk.disk_type(), "SSD");
+    assert_eq!(mounts[0].fs_label_lossy().as_deref(), Some("DATA"));
+    assert!(mounts[1].disk.is_none());
+}
+
+#[test]
+fn test_stats_through_system_root() {
+    let tree = fake_tree::FakeTree::new("stats");
+    tree.write(
+        "proc/self/mountinfo",
+        "25 1 8:1 / / rw - ext4 /dev/sda1 rw\n\
+         41 25 8:2 / /data rw - ext4 /dev/sda2 rw\n",
+    );
+    tree.mkdir("sys/block");
+    let options = ReadOptions::default()
+        .system_root(tree.root())
+        .stats_timeout(None);
+    let mounts = read_mounts(&options).unwrap();
+    // the root is stated through the system root, where /data isn't mounted
+    assert!(!matches!(
+        mounts[0].stats,
+        Err(StatsError::Unreachable | StatsError::Excluded)
+    ));
+    assert!(matches!(mounts[1].stats, Err(StatsError::Unreachable)));
+}
+
+#[test]
+fn test_nodev_mount_kinds() {
+    let mounts = read_fake_system_mounts("kinds");
+    assert_eq!(mounts[0].kind(), MountKind::Storage);
+    assert_eq!(mounts[1].info.nodev, Some(true));
+    assert_eq!(mounts[1].kind(), MountKind::Pseudo);
+    assert_eq!(mounts[2].info.nodev, Some(true));
+    assert_eq!(mounts[2].kind(), MountKind::Storage);
+}
+
+#[test]
+fn test_non_utf8_label() {
+    let mounts = read_fake_system_mounts("label");
+    assert_eq!(
+        mounts[3].fs_label.as_deref().map(|l| l.as_bytes()),
+        Some(&b"caf\xe9"[..])
+    );
+    assert_eq!(mounts[3].fs_label_lossy().as_deref(), Some("caf\u{FFFD}"));
+}
+
+#[test]
+fn test_loop_disk() {
+    let mounts = read_fake_system_mounts("loop");
+    let disk = mounts[4].disk.as_ref().unwrap();
+    assert!(disk.is_file_backed());
+    assert_eq!(disk.read_only, Some(true));
+}
+
+#[test]
+fn test_mount_disk() {
+    let backing_disk = |name: &str| BackingDisk {
+        disk: Disk::with_name(name),
+        role: DiskRole::Mirror,
+    };
+    let layer = |kind, name: &str| StorageLayer {
+        kind,
+        name: name.to_string(),
+        dm_name: None,
+        depth: 1,
+        disk: None,
+        dm_kind: None,
+        dm_suspended: None,
+        md_array: None,
+    };
+    assert!(mount_disk(&[], &[]).is_none());
+    let backing_disks = [backing_disk("sdb"), backing_disk("sdc")];
+    let layers = [
+        layer(StorageLayerKind::Crypt, "dm-0"),
+        layer(StorageLayerKind::Raid, "md0"),
+    ];
+    let disk = mount_disk(&backing_disks, &layers).unwrap();
+    assert_eq!(disk.name, "sdb");
+    assert!(disk.crypted);
+    assert!(!disk.lvm);
+    assert_eq!(disk.disk_type(), "crypt");
+}
@@ -1,8 +1,15 @@
 use {
+    super::{
+        proc_path,
+        read_proc_mounts::read_proc_mounts,
+        statmount::read_statmount_infos,
+    },
     crate::*,
-    lazy_regex::*,
     snafu::prelude::*,
-    std::path::PathBuf,
+    std::{
+        fs,
+        path::PathBuf,
+    },
 };
 
 #[derive(Debug, Snafu)]
@@ -15,99 +22,124 @@ pub struct ParseMountInfoError {
 impl std::str::FromStr for MountInfo {
     type Err = ParseMountInfoError;
     fn from_str(line: &str) -> Result<Self, Self::Err> {
-        (|| {
-            // this parsing is based on `man 5 proc`
-            // Structure is also visible at
-            //  https://man7.org/linux/man-pages/man5/proc_pid_mountinfo.5.html
-            let mut tokens = line.split_whitespace();
-
-            let id = tokens.next()?.parse().ok()?;
-            let parent = tokens.next()?.parse().ok()?;
-
-            // while linux mountinfo need an id and a parent id, they're optional in
-            // the more global model
-            let id = Some(id);
-            let parent = Some(parent);
-
-            let dev = tokens.next()?.parse().ok()?;
-            let root = str_to_pathbuf(tokens.next()?);
-            let mount_point = str_to_pathbuf(tokens.next()?);
-
-            let direct_options =
-                regex_captures_iter!("(?:^|,)([^=,]+)(?:=([^=,]*))?", tokens.next()?,);
-
-            let mut options: Vec<MountOption> = direct_options
-                .map(|c| {
-                    let name = c.get(1).unwrap().as_str().to_string();
-                    let value = c.get(2).map(|v| v.as_str().to_string());
-                    MountOption { name, value }
-                })
-                .collect();
-
-            // skip optional fields in the form name:value where
-            // name can be "shared", "master", "propagate_for", or "unbindable"
-            loop {
-                let token = tokens.next()?;
-                if token == "-" {
-                    break;
-                }
-            }
-
-            let fs_type = tokens.next()?.to_string();
-            let fs = tokens.next()?.to_string();
-
-            if let Some(super_options) = tokens.next() {
-                for c in regex_captures_iter!("(?:^|,)([^=,]+)(?:=([^=,]*))?", super_options) {
-                    let name = c.get(1).unwrap().as_str().to_string();
-                    if name == "rw" {
-                        continue; // rw at super level is not relevant
-                    }
-                    if options.iter().any(|o| o.name == name) {
-                        continue;
-                    }
-                    let value = c.get(2).map(|v| v.as_str().to_string());
-                    options.push(MountOption { name, value });
-                }
-            }
-
-            Some(Self {
-                id,
-                parent,
-                dev,
-                root,
-                mount_point,
-                options,
-                fs,
-                fs_type,
-                bound: false, // determined by post-treatment
-            })
-        })()
-        .with_context(|| ParseMountInfoSnafu { line })
+        parse_mountinfo_line(line.as_bytes())
     }
 }
 
-/// convert a string to a pathbuf, converting ascii-octal encoded
-/// chars.
-/// This is necessary because some chars are encoded. For example
-/// the `/media/dys/USB DISK` is present as `/media/dys/USB\040DISK`
+/// parse a line of /proc/self/mountinfo, which isn't guaranteed to be
+/// valid UTF-8 as paths are written as raw bytes, only some chars being
+/// escaped. For example `/media/dys/USB DISK` is present as
+/// `/media/dys/USB\040DISK`.
 #[cfg(target_os = "linux")]
-fn str_to_pathbuf(s: &str) -> PathBuf {
-    PathBuf::from(sys::decode_string(s))
+pub fn parse_mountinfo_line(line: &[u8]) -> Result<MountInfo, ParseMountInfoError> {
+    fn utf8(field: &[u8]) -> Option<&str> {
+        std::str::from_utf8(field).ok()
+    }
+    (|| {
+        // this parsing is based on `man 5 proc`
+        // Structure is also visible at
+        //  https://man7.org/linux/man-pages/man5/proc_pid_mountinfo.5.html
+        let mut tokens = sys::split_fields(line);
+
+        let id = utf8(tokens.next()?)?.parse().ok()?;
+        let parent = utf8(tokens.next()?)?.parse().ok()?;
+
+        // while linux mountinfo need an id and a parent id, they're optional in
+        // the more global model
+        let id = Some(id);
+        let parent = Some(parent);
+
+        let dev = utf8(tokens.next()?)?.parse().ok()?;
+        let root = PathBuf::from(sys::decode_os_string(tokens.next()?));
+        let mount_point = PathBuf::from(sys::decode_os_string(tokens.next()?));
+
+        let mount_options = MountOption::parse_list(tokens.next()?);
+
+        // optional fields in the form name:value where
+        // name can be "shared", "master", "propagate_from", or "unbindable".
+        // Unknown fields are ignored, as recommended by `man 5 proc`
+        let mut propagation = Propagation::default();
+        loop {
+            let token = tokens.next()?;
+            if token == b"-" {
+                break;
+            }
+            if let Some(token) = utf8(token) {
+                propagation.apply_field(token);
+            }
+        }
+
+        let fs_type = sys::decode_string(tokens.next()?);
+        let fs = sys::decode_string(tokens.next()?);
+
+        let super_options = tokens
+            .next()
+            .map(MountOption::parse_list)
+            .unwrap_or_default();
+
+        Some(MountInfo {
+            id,
+            parent,
+            unique_id: None,
+            dev,
+            root,
+            mount_point,
+            mount_options,
+            super_options,
+            fs,
+            fs_type,
+            nodev: None,        // determined by post-treatment
+            bound: false,       // determined by post-treatment
+            bound_reason: None, // determined by post-treatment
+            bind_source: None,  // determined by post-treatment
+            propagation,
+            shadowed: false, // determined by post-treatment
+        })
+    })()
+    .with_context(|| ParseMountInfoSnafu {
+        line: String::from_utf8_lossy(line),
+    })
 }
 
-/// read all the mount points
+/// read all the mount points, as seen by the process of the options
+/// (by default the current one)
+///
+/// When mountinfo can't be read and no strategy was chosen,
+/// /proc/self/mounts and /etc/mtab are tried instead. With the
+/// statmount strategy, the syscalls are tried before mountinfo.
 #[cfg(target_os = "linux")]
-pub fn read_all_mountinfos() -> Result<Vec<MountInfo>, Error> {
+pub fn read_all_mountinfos(options: &ReadOptions) -> Result<Vec<MountInfo>, Error> {
+    if matches!(options.strategy, Some(Strategy::ProcMounts)) {
+        return read_proc_mounts(options);
+    }
+    let use_statmount = matches!(options.strategy, Some(Strategy::Statmount))
+        && options.pid.is_none()
+        && options.system_root.is_none();
+    if use_statmount {
+        if let Ok(mut mounts) = read_statmount_infos() {
+            compute_bound(&mut mounts);
+            compute_shadowing(&mut mounts);
+            return Ok(mounts);
+        }
+    }
+    let path = proc_path(options, "mountinfo");
+    let file_content = match fs::read(&path) {
+        Ok(content) => content,
+        Err(_) if options.strategy.is_none() => {
+            return read_proc_mounts(options);
+        }
+        Err(source) => {
+            return Err(Error::CantReadFile { source, path });
+        }
+    };
     let mut mounts: Vec<MountInfo> = Vec::new();
-    let path = "/proc/self/mountinfo";
-    let file_content = sys::read_file(path).context(CantReadDirSnafu { path })?;
-    for line in file_content.trim().split('\n') {
-        let mut mount: MountInfo = line
-            .parse()
-            .map_err(|source| Error::ParseMountInfo { source })?;
-        mount.bound = mounts.iter().any(|m| m.dev == mount.dev);
+    for line in sys::non_blank_lines(&file_content) {
+        let mount =
+            parse_mountinfo_line(line).map_err(|source| Error::ParseMountInfo { source })?;
         mounts.push(mount);
     }
+    compute_bound(&mut mounts);
+    compute_shadowing(&mut mounts);
     Ok(mounts)
 }
 
@@ -134,7 +166,7 @@ fn test_from_str() {
     assert_eq!(mi.dev, DeviceId::new(8, 17));
     assert_eq!(&mi.fs, "/dev/sdb1");
     assert_eq!(&mi.fs_type, "btrfs");
-    let mut options = mi.options.clone().into_iter();
+    let mut options = mi.mount_options.clone().into_iter();
     assert_eq!(options.next(), Some(MountOption::new("rw", None)),);
     assert_eq!(options.next(), Some(MountOption::new("noatime", None)));
     assert_eq!(
@@ -145,6 +177,7 @@ fn test_from_str() {
     assert_eq!(mi.has_option("relatime"), false);
     assert_eq!(mi.option_value("thing"), None);
     assert_eq!(mi.option_value("compress"), Some("zstd:3"));
+    assert_eq!(mi.super_options.len(), 4);
     assert_eq!(
         mi.options_string(),
         "rw,noatime,compress=zstd:3,attr2,inode64,noquota".to_string()
@@ -160,3 +193,132 @@ fn test_from_str() {
             .to_string()
     );
 }
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_propagation_from_str() {
+    use std::str::FromStr;
+
+    let mi = MountInfo::from_str(
+        "47 21 0:41 / /dev/hugepages rw,relatime shared:27 - hugetlbfs hugetlbfs rw,pagesize=2M",
+    )
+    .unwrap();
+    assert!(mi.propagation.is_shared());
+    assert_eq!(mi.propagation.shared, Some(27));
+    assert_eq!(mi.propagation.master, None);
+
+    let mi = MountInfo::from_str(
+        "2408 2379 0:25 / /sys rw,nosuid shared:801 master:9 propagate_from:4 - sysfs sysfs rw",
+    )
+    .unwrap();
+    assert_eq!(mi.propagation.shared, Some(801));
+    assert_eq!(mi.propagation.master, Some(9));
+    assert_eq!(mi.propagation.propagate_from, Some(4));
+    assert!(mi.propagation.is_slave());
+    assert_eq!(mi.fs_type, "sysfs");
+
+    let mi = MountInfo::from_str(
+        "3104 2408 0:55 / /sys/fs/cgroup ro,nosuid unbindable some_future_tag:3 - cgroup2 cgroup2 rw",
+    )
+    .unwrap();
+    assert!(mi.propagation.unbindable);
+    assert!(!mi.propagation.is_private());
+
+    let mi = MountInfo::from_str("36 35 98:0 /mnt1 /mnt2 rw,noatime - ext3 /dev/root rw").unwrap();
+    assert_eq!(mi.propagation, Propagation::default());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_read_only_bind_from_str() {
+    use std::str::FromStr;
+
+    let mi = MountInfo::from_str(
+        "512 25 8:1 /srv/data /mnt/data ro,relatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro",
+    )
+    .unwrap();
+    assert_eq!(
+        mi.mount_options,
+        vec![
+            MountOption::new("ro", None),
+            MountOption::new("relatime", None)
+        ]
+    );
+    assert_eq!(mi.super_options[0], MountOption::new("rw", None));
+    assert!(mi.is_read_only());
+    assert!(!mi.is_fs_read_only());
+    assert_eq!(mi.options_string(), "ro,relatime,errors=remount-ro");
+
+    let mi = MountInfo::from_str(
+        "513 25 7:3 / /snap/core/123 ro,nodev,relatime shared:2 - squashfs /dev/loop3 ro,errors=continue",
+    )
+    .unwrap();
+    assert!(mi.is_read_only());
+    assert!(mi.is_fs_read_only());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_mountinfo_line_round_trip() {
+    use std::str::FromStr;
+    let lines = [
+        "47 21 0:41 / /dev/hugepages rw,relatime shared:27 - hugetlbfs hugetlbfs rw,pagesize=2M",
+        "106 26 8:17 / /home/dys/dev rw,noatime,compress=zstd:3 shared:57 - btrfs /dev/sdb1 rw,attr2,inode64,noquota",
+        "73 2 0:33 /root / rw,relatime shared:1 - btrfs /dev/vda3 rw,seclabel,compress=zstd:1,ssd,space_cache=v2,subvolid=256,subvol=/root",
+        "2408 2379 0:25 / /sys rw,nosuid shared:801 master:9 propagate_from:4 - sysfs sysfs rw",
+        "3104 2408 0:55 / /sys/fs/cgroup ro,nosuid unbindable - cgroup2 cgroup2 rw",
+        "36 35 98:0 /mnt1 /mnt2 rw,noatime - ext3 /dev/root rw",
+        "512 25 8:1 /srv/data /mnt/data ro,relatime shared:1 - ext4 /dev/sda1 rw,errors=remount-ro",
+        "600 25 8:33 / /media/dys/USB\\040DISK rw,nosuid,nodev - vfat /dev/sdc1 rw,uid=1000",
+        "700 25 0:60 / /merged rw - overlay overlay rw,lowerdir=/l\\054a:/l=b,upperdir=/u\\040c",
+    ];
+    for line in lines {
+        let mi = MountInfo::from_str(line).unwrap();
+        assert_eq!(mi.to_mountinfo_line(), line);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_escaped_options() {
+    use std::str::FromStr;
+    // the kernel escapes the commas of values, not their equal signs
+    let mi = MountInfo::from_str(
+        "700 25 0:60 / /merged rw - overlay overlay rw,lowerdir=/l\\054a:/l=b,upperdir=/u\\040c",
+    )
+    .unwrap();
+    assert_eq!(mi.super_options.len(), 3);
+    assert_eq!(mi.option_value("lowerdir"), Some("/l,a:/l=b"));
+    assert_eq!(mi.option_value("upperdir"), Some("/u c"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_non_utf8_mountinfo_line() {
+    use std::os::unix::ffi::OsStrExt;
+    let line =
+        b"600 25 8:33 / /media/\xe7\x94\xa8\xe6\x88\xb7/a\\134b\\011c\xff rw - vfat /dev/sdc1 rw";
+    let mi = parse_mountinfo_line(line).unwrap();
+    assert_eq!(
+        mi.mount_point.as_os_str().as_bytes(),
+        b"/media/\xe7\x94\xa8\xe6\x88\xb7/a\\b\tc\xff"
+    );
+    assert_eq!(
+        mi.to_mountinfo_line(),
+        "600 25 8:33 / /media/用户/a\\134b\\011c\\377 rw - vfat /dev/sdc1 rw"
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_mountinfo_fallback() {
+    let tree = super::fake_tree::FakeTree::new("fallback");
+    tree.write("proc/self/mounts", "/dev/sda1 / ext4 rw,relatime 0 0\n");
+    let options = ReadOptions::default()
+        .system_root(tree.root())
+        .stats_timeout(None);
+    let fallback = read_all_mountinfos(&options);
+    let explicit = read_all_mountinfos(&options.strategy(Strategy::Mountinfo));
+    assert_eq!(fallback.unwrap().len(), 1);
+    assert!(matches!(explicit, Err(Error::CantReadFile { .. })));
+}
@@ -0,0 +1,149 @@
+use {
+    super::{
+        proc_path,
+        read_device_id,
+        stats_path,
+    },
+    crate::*,
+    std::{
+        fs,
+        path::PathBuf,
+    },
+};
+
+/// Parse a line of /proc/mounts or /etc/mtab, which have the format
+/// of fstab: `<spec> <mount point> <type> <options> <dump> <pass>`.
+///
+/// The returned mount info has no id, no parent, and a device
+/// id to be filled by the caller.
+fn parse_proc_mounts_line(line: &[u8]) -> Option<MountInfo> {
+    let mut tokens = sys::split_fields(line);
+    let fs = sys::decode_string(tokens.next()?);
+    let mount_point = PathBuf::from(sys::decode_os_string(tokens.next()?));
+    let fs_type = sys::decode_string(tokens.next()?);
+    // options of the mount point and of the superblock are mixed here
+    let mount_options = MountOption::parse_list(tokens.next()?);
+    Some(MountInfo {
+        id: None,
+        parent: None,
+        unique_id: None,
+        dev: DeviceId::new(0, 0), // unknown at this point
+        root: PathBuf::from("/"), // unknown, we assume the whole fs is mounted
+        mount_point,
+        mount_options,
+        super_options: Vec::new(),
+        fs,
+        fs_type,
+        nodev: None,
+        bound: false,
+        bound_reason: None, // the root isn't known
+        bind_source: None,
+        propagation: Propagation::default(),
+        shadowed: false,
+    })
+}
+
+/// Read all the mount points from /proc/self/mounts, or from /etc/mtab
+/// when it's not available.
+///
+/// When a pid is set, /proc/<pid>/mounts is read and there's no fallback,
+/// as /etc/mtab doesn't tell the mounts of another process.
+///
+/// Device ids are found by stating the mount points and are left to 0:0
+/// when this fails.
+pub fn read_proc_mounts(options: &ReadOptions) -> Result<Vec<MountInfo>, Error> {
+    let path = proc_path(options, "mounts");
+    let file_content = match fs::read(&path) {
+        Ok(content) => content,
+        Err(source) if options.pid.is_some() => {
+            return Err(Error::CantReadFile { source, path });
+        }
+        Err(_) => {
+            let path = options.system_path("/etc/mtab");
+            fs::read(&path).map_err(|source| Error::CantReadFile { source, path })?
+        }
+    };
+    let mut mounts: Vec<MountInfo> = Vec::new();
+    for line in sys::non_blank_lines(&file_content) {
+        if line.trim_ascii_start().starts_with(b"#") {
+            continue;
+        }
+        let mut mount = parse_proc_mounts_line(line).ok_or(Error::UnexpectedFormat)?;
+        let dev = if mount.is_remote() && !options.remote_stats {
+            None
+        } else {
+            read_device_id(&stats_path(options, &mount.mount_point), options)
+        };
+        if let Some(dev) = dev {
+            mount.dev = dev;
+        }
+        mounts.push(mount);
+    }
+    compute_bound_without_roots(&mut mounts);
+    Ok(mounts)
+}
+
+/// Set the `bound` field of mount infos whose root isn't known, with the
+/// same order as for mountinfo, so not depending on the order of lines.
+///
+/// As roots are all assumed to be "/", the reason and the source of the
+/// bind can't be known and are left empty.
+fn compute_bound_without_roots(mounts: &mut [MountInfo]) {
+    compute_bound(mounts);
+    for mount in mounts {
+        if mount.dev == DeviceId::new(0, 0) {
+            mount.bound = false; // device id unknown
+        }
+        mount.bound_reason = None;
+        mount.bind_source = None;
+    }
+}
+
+#[test]
+fn test_parse_proc_mounts_line() {
+    let mi = parse_proc_mounts_line(
+        b"/dev/sdb1 /media/dys/USB\\040DISK vfat rw,nosuid,nodev,relatime,uid=1000,iocharset=utf8 0 0",
+    )
+    .unwrap();
+    assert_eq!(mi.id, None);
+    assert_eq!(mi.fs, "/dev/sdb1");
+    assert_eq!(mi.fs_type, "vfat");
+    assert_eq!(mi.mount_point, PathBuf::from("/media/dys/USB DISK"));
+    assert_eq!(mi.option_value("uid"), Some("1000"));
+    assert_eq!(
+        mi.options_string(),
+        "rw,nosuid,nodev,relatime,uid=1000,iocharset=utf8"
+    );
+    assert!(parse_proc_mounts_line(b"proc").is_none());
+}
+
+#[test]
+fn test_proc_mounts_bound() {
+    let mount = |line: &[u8], dev: &str| MountInfo {
+        dev: dev.parse().unwrap(),
+        ..parse_proc_mounts_line(line).unwrap()
+    };
+    let mut mounts = vec![
+        mount(b"/dev/sda1 /srv/data/www ext4 rw 0 0", "8:1"),
+        mount(b"/dev/sda1 /srv ext4 rw 0 0", "8:1"),
+        mount(b"server:/share /mnt/share nfs rw 0 0", "0:0"),
+        mount(b"server:/other /mnt/other nfs rw 0 0", "0:0"),
+    ];
+    compute_bound_without_roots(&mut mounts);
+    let bound: Vec<bool> = mounts.iter().map(|m| m.bound).collect();
+    assert_eq!(bound, vec![true, false, false, false]);
+    assert!(mounts.iter().all(|m| m.bound_reason.is_none()));
+    assert!(mounts.iter().all(|m| m.bind_source.is_none()));
+}
+
+#[test]
+fn test_no_mtab_fallback_for_pid() {
+    let tree = super::fake_tree::FakeTree::new("mtab");
+    tree.write("etc/mtab", "/dev/sda1 / ext4 rw,relatime 0 0\n");
+    let options = ReadOptions::default()
+        .system_root(tree.root())
+        .stats_timeout(None);
+    assert_eq!(read_proc_mounts(&options).unwrap().len(), 1);
+    let for_pid = read_proc_mounts(&options.pid(1));
+    assert!(matches!(for_pid, Err(Error::CantReadFile { .. })));
+}
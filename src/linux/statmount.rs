@@ -0,0 +1,379 @@
+//! Reading of the mounts with the listmount and statmount syscalls,
+//! available since linux 6.8
+//!
+//! The layouts are the ones of `include/uapi/linux/mount.h`.
+
+use {
+    crate::*,
+    std::{
+        ffi::OsStr,
+        io,
+        os::unix::ffi::OsStrExt,
+        path::PathBuf,
+    },
+};
+
+// the syscall numbers are the same on all architectures supported by
+// rust (alpha, which isn't, has them shifted by 110)
+const SYS_STATMOUNT: libc::c_long = 457;
+const SYS_LISTMOUNT: libc::c_long = 458;
+
+/// size of the first version of `struct mnt_id_req`
+const MNT_ID_REQ_SIZE_VER0: u32 = 24;
+/// id to give to listmount to list the mounts from the root
+const LSMT_ROOT: u64 = u64::MAX;
+
+const STATMOUNT_SB_BASIC: u64 = 0x1;
+const STATMOUNT_MNT_BASIC: u64 = 0x2;
+const STATMOUNT_PROPAGATE_FROM: u64 = 0x4;
+const STATMOUNT_MNT_ROOT: u64 = 0x8;
+const STATMOUNT_MNT_POINT: u64 = 0x10;
+const STATMOUNT_FS_TYPE: u64 = 0x20;
+const STATMOUNT_MNT_OPTS: u64 = 0x80;
+const STATMOUNT_FS_SUBTYPE: u64 = 0x100;
+const STATMOUNT_SB_SOURCE: u64 = 0x200;
+/// the parts without which a mount info as complete as the one of
+/// mountinfo can't be built (the last ones are missing before linux 6.11)
+const STATMOUNT_REQUIRED: u64 = STATMOUNT_SB_BASIC
+    | STATMOUNT_MNT_BASIC
+    | STATMOUNT_MNT_ROOT
+    | STATMOUNT_MNT_POINT
+    | STATMOUNT_FS_TYPE
+    | STATMOUNT_MNT_OPTS
+    | STATMOUNT_FS_SUBTYPE
+    | STATMOUNT_SB_SOURCE;
+
+const SB_RDONLY: u32 = 0x1;
+const SB_SYNCHRONOUS: u32 = 0x10;
+const SB_MANDLOCK: u32 = 0x40;
+const SB_DIRSYNC: u32 = 0x80;
+const SB_LAZYTIME: u32 = 0x0200_0000;
+
+const MOUNT_ATTR_RDONLY: u64 = 0x1;
+const MOUNT_ATTR_NOSUID: u64 = 0x2;
+const MOUNT_ATTR_NODEV: u64 = 0x4;
+const MOUNT_ATTR_NOEXEC: u64 = 0x8;
+const MOUNT_ATTR__ATIME: u64 = 0x70;
+const MOUNT_ATTR_RELATIME: u64 = 0x0;
+const MOUNT_ATTR_NOATIME: u64 = 0x10;
+const MOUNT_ATTR_NODIRATIME: u64 = 0x80;
+const MOUNT_ATTR_IDMAP: u64 = 0x0010_0000;
+const MOUNT_ATTR_NOSYMFOLLOW: u64 = 0x0020_0000;
+
+const MS_UNBINDABLE: u64 = 1 << 17;
+const MS_SLAVE: u64 = 1 << 19;
+const MS_SHARED: u64 = 1 << 20;
+
+/// size of the fixed part of `struct statmount`, before the strings
+const STATMOUNT_HEADER_SIZE: usize = 512;
+
+#[repr(C)]
+struct MntIdReq {
+    size: u32,
+    spare: u32,
+    mnt_id: u64,
+    param: u64,
+}
+
+impl MntIdReq {
+    fn new(
+        mnt_id: u64,
+        param: u64,
+    ) -> Self {
+        Self {
+            size: MNT_ID_REQ_SIZE_VER0,
+            spare: 0,
+            mnt_id,
+            param,
+        }
+    }
+}
+
+/// List the unique ids of all the mounts of the current mount namespace
+fn list_mount_ids() -> io::Result<Vec<u64>> {
+    let mut ids = Vec::new();
+    let mut buf = vec![0u64; 512];
+    loop {
+        // the mounts after the last received id are listed
+        let req = MntIdReq::new(LSMT_ROOT, ids.last().copied().unwrap_or(0));
+        let count = unsafe {
+            libc::syscall(
+                SYS_LISTMOUNT,
+                &req as *const MntIdReq,
+                buf.as_mut_ptr(),
+                buf.len(),
+                0,
+            )
+        };
+        if count < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let count = count as usize;
+        ids.extend_from_slice(&buf[..count]);
+        if count < buf.len() {
+            return Ok(ids);
+        }
+    }
+}
+
+/// The raw answer of statmount, with accessors to its fields
+struct StatmountBuf {
+    /// a buffer of u64 to ensure the alignment
+    buf: Vec<u64>,
+}
+
+impl StatmountBuf {
+    fn read(mnt_id: u64) -> io::Result<Self> {
+        let mask = STATMOUNT_REQUIRED | STATMOUNT_PROPAGATE_FROM;
+        let req = MntIdReq::new(mnt_id, mask);
+        let mut buf = vec![0u64; 1024];
+        loop {
+            let size = buf.len() * 8;
+            let code = unsafe {
+                libc::syscall(
+                    SYS_STATMOUNT,
+                    &req as *const MntIdReq,
+                    buf.as_mut_ptr(),
+                    size,
+                    0,
+                )
+            };
+            if code == 0 {
+                return Ok(Self { buf });
+            }
+            let e = io::Error::last_os_error();
+            if e.raw_os_error() != Some(libc::EOVERFLOW) || buf.len() > 1 << 20 {
+                return Err(e);
+            }
+            buf.resize(buf.len() * 4, 0);
+        }
+    }
+    fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.buf.as_ptr() as *const u8, self.buf.len() * 8) }
+    }
+    fn u32_at(
+        &self,
+        offset: usize,
+    ) -> u32 {
+        u32::from_ne_bytes(self.bytes()[offset..offset + 4].try_into().unwrap())
+    }
+    fn u64_at(
+        &self,
+        offset: usize,
+    ) -> u64 {
+        u64::from_ne_bytes(self.bytes()[offset..offset + 8].try_into().unwrap())
+    }
+    fn mask(&self) -> u64 {
+        self.u64_at(8)
+    }
+    /// return the nul terminated string whose offset (in the string part)
+    /// is at the given offset, if the part of the given mask was returned
+    fn str_at(
+        &self,
+        offset: usize,
+        part: u64,
+    ) -> Option<&[u8]> {
+        if self.mask() & part == 0 {
+            return None;
+        }
+        let bytes = self.bytes();
+        let start = STATMOUNT_HEADER_SIZE + self.u32_at(offset) as usize;
+        let len = bytes.get(start..)?.iter().position(|&b| b == 0)?;
+        Some(&bytes[start..start + len])
+    }
+    fn to_mount_info(&self) -> Option<MountInfo> {
+        let mask = self.mask();
+        if mask & STATMOUNT_REQUIRED != STATMOUNT_REQUIRED {
+            return None;
+        }
+        let sb_flags = self.u32_at(32);
+        let mnt_attr = self.u64_at(64);
+        let mnt_propagation = self.u64_at(72);
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).to_string();
+        let path = |bytes: &[u8]| PathBuf::from(OsStr::from_bytes(bytes));
+        let mut fs_type = lossy(self.str_at(36, STATMOUNT_FS_TYPE)?);
+        let subtype = self.str_at(120, STATMOUNT_FS_SUBTYPE)?;
+        if !subtype.is_empty() {
+            fs_type.push('.');
+            fs_type.push_str(&String::from_utf8_lossy(subtype));
+        }
+        let propagation = Propagation {
+            shared: (mnt_propagation & MS_SHARED != 0).then(|| self.u64_at(80) as PeerGroupId),
+            master: (mnt_propagation & MS_SLAVE != 0).then(|| self.u64_at(88) as PeerGroupId),
+            propagate_from: (mask & STATMOUNT_PROPAGATE_FROM != 0)
+                .then(|| self.u64_at(96) as PeerGroupId)
+                .filter(|&id| id != 0),
+            unbindable: mnt_propagation & MS_UNBINDABLE != 0,
+        };
+        let sb_dev_major = self.u32_at(16);
+        let sb_dev_minor = self.u32_at(20);
+        Some(MountInfo {
+            id: Some(self.u32_at(56)),
+            parent: Some(self.u32_at(60)),
+            unique_id: Some(self.u64_at(40)),
+            dev: DeviceId::new(sb_dev_major, sb_dev_minor),
+            root: path(self.str_at(104, STATMOUNT_MNT_ROOT)?),
+            mount_point: path(self.str_at(108, STATMOUNT_MNT_POINT)?),
+            mount_options: mount_options(mnt_attr),
+            super_options: super_options(sb_flags, self.str_at(4, STATMOUNT_MNT_OPTS)?),
+            fs: Some(self.str_at(124, STATMOUNT_SB_SOURCE)?)
+                .filter(|source| !source.is_empty())
+                .map_or_else(|| "none".to_string(), lossy),
+            fs_type,
+            nodev: None,
+            bound: false,
+            bound_reason: None,
+            bind_source: None,
+            propagation,
+            shadowed: false,
+        })
+    }
+}
+
+/// build the options of the mount point, in the order of mountinfo
+fn mount_options(mnt_attr: u64) -> Vec<MountOption> {
+    let atime = mnt_attr & MOUNT_ATTR__ATIME;
+    let flags = [
+        (mnt_attr & MOUNT_ATTR_RDONLY != 0, "ro"),
+        (mnt_attr & MOUNT_ATTR_RDONLY == 0, "rw"),
+        (mnt_attr & MOUNT_ATTR_NOSUID != 0, "nosuid"),
+        (mnt_attr & MOUNT_ATTR_NODEV != 0, "nodev"),
+        (mnt_attr & MOUNT_ATTR_NOEXEC != 0, "noexec"),
+        (atime == MOUNT_ATTR_NOATIME, "noatime"),
+        (mnt_attr & MOUNT_ATTR_NODIRATIME != 0, "nodiratime"),
+        (atime == MOUNT_ATTR_RELATIME, "relatime"),
+        (mnt_attr & MOUNT_ATTR_NOSYMFOLLOW != 0, "nosymfollow"),
+        (mnt_attr & MOUNT_ATTR_IDMAP != 0, "idmapped"),
+    ];
+    flags
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| MountOption::new(name, None))
+        .collect()
+}
+
+/// build the options of the filesystem, in the order of mountinfo
+fn super_options(
+    sb_flags: u32,
+    fs_options: &[u8],
+) -> Vec<MountOption> {
+    let flags = [
+        (sb_flags & SB_RDONLY != 0, "ro"),
+        (sb_flags & SB_RDONLY == 0, "rw"),
+        (sb_flags & SB_SYNCHRONOUS != 0, "sync"),
+        (sb_flags & SB_DIRSYNC != 0, "dirsync"),
+        (sb_flags & SB_MANDLOCK != 0, "mand"),
+        (sb_flags & SB_LAZYTIME != 0, "lazytime"),
+    ];
+    let mut options: Vec<MountOption> = flags
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .map(|(_, name)| MountOption::new(name, None))
+        .collect();
+    options.extend(MountOption::parse_list(fs_options));
+    options
+}
+
+/// Read the mounts of the current mount namespace with the listmount
+/// and statmount syscalls, in the order of their creation.
+///
+/// Mounts disappearing while they're read are skipped. An error is
+/// returned when the kernel doesn't give all the parts found in
+/// mountinfo, so that the caller can fall back to mountinfo.
+pub(crate) fn read_statmount_infos() -> io::Result<Vec<MountInfo>> {
+    let mut infos = Vec::new();
+    for mnt_id in list_mount_ids()? {
+        let buf = match StatmountBuf::read(mnt_id) {
+            Ok(buf) => buf,
+            Err(e) if e.raw_os_error() == Some(libc::ENOENT) => continue,
+            Err(e) => return Err(e),
+        };
+        let info = buf.to_mount_info().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Unsupported, "incomplete statmount answer")
+        })?;
+        infos.push(info);
+    }
+    Ok(infos)
+}
+
+#[test]
+fn test_mount_options_from_attributes() {
+    let names = |mnt_attr: u64| -> Vec<String> {
+        mount_options(mnt_attr)
+            .into_iter()
+            .map(|o| o.name)
+            .collect()
+    };
+    assert_eq!(names(0), vec!["rw", "relatime"]);
+    assert_eq!(
+        names(MOUNT_ATTR_RDONLY | MOUNT_ATTR_NOSUID | MOUNT_ATTR_NODEV | MOUNT_ATTR_NOATIME),
+        vec!["ro", "nosuid", "nodev", "noatime"]
+    );
+    let options = super_options(SB_LAZYTIME, b"compress=zstd:3,ssd,subvol=/@");
+    assert_eq!(
+        options_list_string(&options),
+        "rw,lazytime,compress=zstd:3,ssd,subvol=/@"
+    );
+}
+
+#[test]
+fn test_decode_statmount() {
+    let mut bytes = vec![0u8; STATMOUNT_HEADER_SIZE];
+    let mut strings = Vec::new();
+    let set = |bytes: &mut Vec<u8>, offset: usize, value: &[u8]| {
+        bytes[offset..offset + value.len()].copy_from_slice(value);
+    };
+    let add_str = |strings: &mut Vec<u8>, s: &str| {
+        let offset = strings.len() as u32;
+        strings.extend_from_slice(s.as_bytes());
+        strings.push(0);
+        offset.to_ne_bytes()
+    };
+    let mask = STATMOUNT_REQUIRED | STATMOUNT_PROPAGATE_FROM;
+    set(&mut bytes, 8, &mask.to_ne_bytes());
+    set(&mut bytes, 16, &8u32.to_ne_bytes());
+    set(&mut bytes, 20, &1u32.to_ne_bytes());
+    set(&mut bytes, 32, &0u32.to_ne_bytes());
+    set(&mut bytes, 40, &0x1_0000_0042u64.to_ne_bytes());
+    set(&mut bytes, 56, &42u32.to_ne_bytes());
+    set(&mut bytes, 60, &1u32.to_ne_bytes());
+    set(
+        &mut bytes,
+        64,
+        &(MOUNT_ATTR_NOSUID | MOUNT_ATTR_NOATIME).to_ne_bytes(),
+    );
+    set(&mut bytes, 72, &(MS_SHARED | MS_SLAVE).to_ne_bytes());
+    set(&mut bytes, 80, &3u64.to_ne_bytes());
+    set(&mut bytes, 88, &5u64.to_ne_bytes());
+    set(&mut bytes, 96, &0u64.to_ne_bytes());
+    let offset = add_str(&mut strings, "errors=remount-ro");
+    set(&mut bytes, 4, &offset);
+    let offset = add_str(&mut strings, "ext4");
+    set(&mut bytes, 36, &offset);
+    let offset = add_str(&mut strings, "/");
+    set(&mut bytes, 104, &offset);
+    let offset = add_str(&mut strings, "/home");
+    set(&mut bytes, 108, &offset);
+    let offset = add_str(&mut strings, "");
+    set(&mut bytes, 120, &offset);
+    let offset = add_str(&mut strings, "/dev/sda1");
+    set(&mut bytes, 124, &offset);
+    bytes.extend_from_slice(&strings);
+    bytes.resize(bytes.len().div_ceil(8) * 8, 0);
+    let buf = StatmountBuf {
+        buf: bytes
+            .chunks(8)
+            .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect(),
+    };
+    let info = buf.to_mount_info().unwrap();
+    assert_eq!(
+        info.to_mountinfo_line(),
+        "42 1 8:1 / /home rw,nosuid,noatime shared:3 master:5 - ext4 /dev/sda1 rw,errors=remount-ro",
+    );
+    assert_eq!(info.unique_id, Some(0x1_0000_0042));
+    // kernels before 6.11 don't give the source, subtype and fs options
+    let mut buf = buf;
+    buf.buf[1] &= !STATMOUNT_SB_SOURCE;
+    assert!(buf.to_mount_info().is_none());
+}
@@ -0,0 +1,209 @@
+use {
+    super::{
+        proc_path,
+        read_mountinfos::read_all_mountinfos,
+        read_mounts,
+    },
+    crate::*,
+    snafu::prelude::*,
+    std::{
+        collections::VecDeque,
+        fs::File,
+        io,
+        os::unix::io::AsRawFd,
+        sync::mpsc,
+        thread,
+        time::Duration,
+    },
+};
+
+/// A change in the mount table
+#[derive(Debug, Clone)]
+pub enum MountEvent {
+    Added(MountInfo),
+    Removed(MountInfo),
+    /// the options of the mount point or of the filesystem changed
+    /// (eg after a `mount -o remount,ro`)
+    OptionsChanged {
+        old: MountInfo,
+        new: MountInfo,
+    },
+    /// the mount point changed (eg after a `mount --move`)
+    Moved {
+        old: MountInfo,
+        new: MountInfo,
+    },
+}
+
+/// A watcher of the mount table, waiting for the kernel to signal
+/// a change on /proc/self/mountinfo, then reading the mount infos
+/// again and computing the changes.
+///
+/// Only the mount infos are read on changes: disks, labels and stats
+/// are read by [MountWatcher::read_mounts], when needed.
+///
+/// Use it as a blocking iterator of events, or call `spawn` to
+/// receive the events in a channel.
+pub struct MountWatcher {
+    file: File,
+    options: ReadOptions,
+    infos: Vec<MountInfo>,
+    pending: VecDeque<MountEvent>,
+}
+
+impl MountWatcher {
+    /// Start watching the mount table of the process of the options
+    /// (by default the current one), reading the current mounts
+    pub fn new(options: ReadOptions) -> Result<Self, Error> {
+        let path = proc_path(&options, "mountinfo");
+        // the file must be opened before reading the mounts, so that
+        // no change is missed
+        let file = File::open(&path).context(CantReadFileSnafu { path })?;
+        let infos = read_all_mountinfos(&options)?;
+        Ok(Self {
+            file,
+            options,
+            infos,
+            pending: VecDeque::new(),
+        })
+    }
+    /// Return the mount infos as read after the last change
+    pub fn mount_infos(&self) -> &[MountInfo] {
+        &self.infos
+    }
+    /// Read the mounts, with their disks, labels and stats, with the
+    /// options of the watcher
+    pub fn read_mounts(&self) -> Result<Vec<Mount>, Error> {
+        read_mounts(&self.options)
+    }
+    /// Wait for a change of the mount table, at most for the given
+    /// duration (or forever if None), then return the resulting events.
+    ///
+    /// The returned list is empty on timeout, or when the change
+    /// didn't affect the mount infos as they're seen by lfs-core.
+    pub fn wait(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<MountEvent>, Error> {
+        if !self.poll(timeout)? {
+            return Ok(Vec::new());
+        }
+        let infos = read_all_mountinfos(&self.options)?;
+        let events = mount_events(&self.infos, &infos);
+        self.infos = infos;
+        Ok(events)
+    }
+    /// Wait for the kernel to signal a change, returning false on timeout
+    fn poll(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<bool, Error> {
+        let mut fd = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLPRI | libc::POLLERR,
+            revents: 0,
+        };
+        let timeout = timeout.map_or(-1, |d| d.as_millis().min(i32::MAX as u128) as i32);
+        loop {
+            let code = unsafe { libc::poll(&mut fd, 1, timeout) };
+            if code >= 0 {
+                return Ok(code > 0);
+            }
+            let e = io::Error::last_os_error();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(Error::CantPoll { source: e });
+            }
+        }
+    }
+    /// Move the watcher to a new thread, and return a receiver of the
+    /// events. The thread stops after sending an error, or when it tries
+    /// to send an event after the receiver was dropped: as it's blocked
+    /// until the next change of the mount table, dropping the receiver
+    /// doesn't end it immediately.
+    pub fn spawn(self) -> mpsc::Receiver<Result<MountEvent, Error>> {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for event in self {
+                let is_err = event.is_err();
+                if tx.send(event).is_err() || is_err {
+                    break;
+                }
+            }
+        });
+        rx
+    }
+}
+
+impl Iterator for MountWatcher {
+    type Item = Result<MountEvent, Error>;
+    /// Return the next event, blocking until there's one
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(Ok(event));
+            }
+            match self.wait(None) {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Compute the events leading from the old mount infos to the new ones
+fn mount_events(
+    old_infos: &[MountInfo],
+    new_infos: &[MountInfo],
+) -> Vec<MountEvent> {
+    let mut events = Vec::new();
+    for old in old_infos {
+        match new_infos.iter().find(|new| new.is_same_mount(old)) {
+            None => events.push(MountEvent::Removed(old.clone())),
+            Some(new) if new.mount_point != old.mount_point => {
+                events.push(MountEvent::Moved {
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+            Some(new)
+                if new.mount_options != old.mount_options
+                    || new.super_options != old.super_options =>
+            {
+                events.push(MountEvent::OptionsChanged {
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for new in new_infos {
+        if !old_infos.iter().any(|old| old.is_same_mount(new)) {
+            events.push(MountEvent::Added(new.clone()));
+        }
+    }
+    events
+}
+
+#[test]
+fn test_mount_events() {
+    let mount = |line: &str| line.parse::<MountInfo>().unwrap();
+    let old_infos = vec![
+        mount("25 1 8:1 / / rw - ext4 /dev/sda1 rw"),
+        mount("40 25 8:2 / /mnt/a rw - ext4 /dev/sda2 rw"),
+        mount("41 25 8:3 / /mnt/b rw - ext4 /dev/sda3 rw"),
+        mount("42 25 8:4 / /mnt/c rw - ext4 /dev/sda4 rw"),
+    ];
+    let new_infos = vec![
+        mount("25 1 8:1 / / rw - ext4 /dev/sda1 rw"),
+        mount("40 25 8:2 / /mnt/a ro - ext4 /dev/sda2 rw"),
+        mount("41 25 8:3 / /mnt/d rw - ext4 /dev/sda3 rw"),
+        mount("42 25 8:5 / /mnt/c rw - ext4 /dev/sda5 rw"), // id reused
+    ];
+    let events = mount_events(&old_infos, &new_infos);
+    assert_eq!(events.len(), 4);
+    assert!(matches!(&events[0], MountEvent::OptionsChanged { new, .. } if new.is_read_only()));
+    assert!(matches!(&events[1], MountEvent::Moved { new, .. } if new.mount_point.ends_with("d")));
+    assert!(matches!(&events[2], MountEvent::Removed(old) if old.fs == "/dev/sda4"));
+    assert!(matches!(&events[3], MountEvent::Added(new) if new.fs == "/dev/sda5"));
+}
@@ -105,13 +105,20 @@ pub fn read_mounts(_options: &ReadOptions) -> Result<Vec<Mount>, Error> {
         let mut info = MountInfo {
             id: None,
             parent: None,
+            unique_id: None,
             dev,
             root: mount_point.clone().into(), // unsure
             mount_point: mount_point.into(),
-            options: Default::default(),
+            mount_options: Default::default(),
+            super_options: Default::default(),
             fs: node,
             fs_type: file_system,
+            nodev: None,
             bound: false, // FIXME unsure (as for root)
+            bound_reason: None,
+            bind_source: None,
+            propagation: Default::default(),
+            shadowed: false,
         };
         if let Some(shortened) = info.fs_type.strip_prefix("MS-DOS ") {
             info.fs_type = shortened.to_string();
@@ -123,6 +130,9 @@ pub fn read_mounts(_options: &ReadOptions) -> Result<Vec<Mount>, Error> {
             stats,
             uuid,
             part_uuid,
+            stacked: None,
+            backing_disks: Vec::new(),
+            layers: Vec::new(),
         };
         mounts.push(mount);
     }
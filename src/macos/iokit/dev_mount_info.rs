@@ -23,13 +23,20 @@ impl DevMountInfo {
         MountInfo {
             id: None,
             parent: None,
+            unique_id: None,
             dev: self.dev,
             root: self.mount_point.clone().into(),
             mount_point: self.mount_point.clone().into(),
-            options: self.options.clone(),
+            mount_options: self.options.clone(),
+            super_options: Vec::new(),
             fs: self.device.clone(),
             fs_type: self.fs_type.clone(),
+            nodev: None,
             bound: false,
+            bound_reason: None,
+            bind_source: None,
+            propagation: Default::default(),
+            shadowed: false,
         }
     }
     pub fn get_all() -> Vec<Self> {
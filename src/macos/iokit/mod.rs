@@ -71,6 +71,9 @@ pub fn read_mounts(_options: &ReadOptions) -> Result<Vec<Mount>, Error> {
             stats: Ok(dmi.stats.clone()),
             uuid: dev.as_ref().and_then(|d| d.uuid.clone()),
             part_uuid: dev.as_ref().and_then(|d| d.part_uuid.clone()),
+            stacked: None,
+            backing_disks: Vec::new(),
+            layers: Vec::new(),
         };
         mounts.push(mount);
     }
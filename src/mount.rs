@@ -1,17 +1,43 @@
-use crate::*;
+use {
+    crate::*,
+    std::{
+        borrow::Cow,
+        ffi::OsString,
+    },
+};
 
 /// A mount point
 #[derive(Debug, Clone)]
 pub struct Mount {
     pub info: MountInfo,
-    pub fs_label: Option<String>,
+    /// the label of the filesystem, which isn't guaranteed to be
+    /// valid UTF-8 (see [Mount::fs_label_lossy])
+    pub fs_label: Option<OsString>,
+    /// the disk of the filesystem. On linux, it's the first of the
+    /// backing disks, sorted by name, when there are several (eg for a
+    /// RAID array), and it's flagged crypted or lvm when the storage
+    /// stack holds such a layer
     pub disk: Option<Disk>,
     pub stats: Result<Stats, StatsError>,
     pub uuid: Option<String>,
     pub part_uuid: Option<String>,
+    /// the layers of a stacked filesystem, like an overlay
+    /// (only computed on linux)
+    pub stacked: Option<StackedSource>,
+    /// the physical disks the filesystem is stored on, with their role
+    /// (only computed on linux)
+    pub backing_disks: Vec<BackingDisk>,
+    /// the storage stack, from the filesystem down to the disks
+    /// (only computed on linux)
+    pub layers: Vec<StorageLayer>,
 }
 
 impl Mount {
+    /// Return the label of the filesystem, invalid UTF-8 sequences
+    /// being replaced with `U+FFFD`
+    pub fn fs_label_lossy(&self) -> Option<Cow<'_, str>> {
+        self.fs_label.as_ref().map(|label| label.to_string_lossy())
+    }
     /// Return inodes information, when available and consistent
     pub fn inodes(&self) -> Option<&Inodes> {
         self.stats
@@ -43,6 +69,21 @@ impl Mount {
         matches!(self.stats, Err(StatsError::Timeout))
     }
 
+    /// Tell whether the two mounts are the same mount, possibly read at
+    /// different times, its mount point or options having changed
+    /// (see [MountInfo::is_same_mount])
+    pub fn is_same_mount(
+        &self,
+        other: &Mount,
+    ) -> bool {
+        self.info.is_same_mount(&other.info)
+    }
+    /// Tell whether the mount is covered by another mount, on the same
+    /// mount point or on a parent one, making it invisible to users
+    pub fn is_shadowed(&self) -> bool {
+        self.info.shadowed
+    }
+
     #[cfg(unix)]
     pub fn is_remote(&self) -> bool {
         self.info.is_remote()
@@ -53,3 +94,22 @@ impl Mount {
         self.disk.as_ref().is_some_and(|disk| disk.remote)
     }
 }
+
+#[cfg(all(test, target_os = "linux"))]
+impl Mount {
+    /// Build a mount from a line of /proc/self/mountinfo, without
+    /// disk, stats or other information
+    pub(crate) fn from_mountinfo_line(line: &str) -> Self {
+        Self {
+            info: line.parse().unwrap(),
+            fs_label: None,
+            disk: None,
+            stats: Err(StatsError::Excluded),
+            uuid: None,
+            part_uuid: None,
+            stacked: None,
+            backing_disks: Vec::new(),
+            layers: Vec::new(),
+        }
+    }
+}
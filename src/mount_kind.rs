@@ -0,0 +1,283 @@
+use {
+    crate::*,
+    std::path::Path,
+};
+
+/// types of filesystems exposing kernel or system data, used when it's
+/// unknown whether the filesystem type needs a device
+static PSEUDO_FS_TYPES: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+static MEMORY_FS_TYPES: &[&str] = &["tmpfs", "ramfs"];
+
+/// types of filesystems which need no device but are made of other
+/// filesystems, so aren't pseudo filesystems
+static UNION_FS_TYPES: &[&str] = &["aufs", "fuse", "fuse-overlayfs", "overlay"];
+
+/// types of filesystems which need no block device but store user data
+/// (pools, like ZFS, or shares of a host, like virtiofs)
+static NODEV_STORAGE_FS_TYPES: &[&str] = &[
+    "9p",
+    "ceph",
+    "glusterfs",
+    "lustre",
+    "prl_fs",
+    "vboxsf",
+    "virtiofs",
+    "zfs",
+];
+
+/// directories where container engines store their layers
+static CONTAINER_DIRS: &[&str] = &[
+    "/var/lib/containerd",
+    "/var/lib/containers",
+    "/var/lib/docker",
+    "/var/lib/lxc",
+    "/var/lib/lxd",
+    "/run/containerd",
+    "/run/containers",
+];
+
+/// mount points of the partitions needed to boot
+static SYSTEM_MOUNT_POINTS: &[&str] = &["/boot", "/efi", "/recovery"];
+
+/// A classification of mounts, telling what they're used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MountKind {
+    /// a virtual filesystem exposing kernel data (proc, sysfs, cgroup, debugfs, etc.)
+    Pseudo,
+    /// a filesystem in memory (tmpfs, ramfs, zram)
+    Memory,
+    /// a layer or the root of a container (overlay in docker or podman storage, etc.)
+    ContainerLayer,
+    /// a read-only image mounted from a loop device (snap, squashfs, disk image)
+    Loop,
+    /// a filesystem needed by the system, like the boot or EFI partition
+    System,
+    /// a network filesystem
+    Remote,
+    /// a filesystem storing user data
+    Storage,
+}
+
+impl MountKind {
+    /// tell whether the mount is likely to be of interest to users
+    /// looking for their storage
+    pub fn is_storage(self) -> bool {
+        matches!(self, Self::Storage | Self::Remote)
+    }
+}
+
+impl MountInfo {
+    /// return the type of the filesystem, without the subtype
+    /// (eg "fuse" for "fuse.sshfs")
+    pub fn fs_base_type(&self) -> &str {
+        self.fs_type.split('.').next().unwrap_or_default()
+    }
+    /// tell whether the mount is a layer or the root of a container
+    fn is_container_layer(&self) -> bool {
+        let in_container_dir = |path: &Path| {
+            CONTAINER_DIRS.iter().any(|dir| path.starts_with(dir))
+                || path.to_string_lossy().contains("/.local/share/containers/")
+        };
+        if in_container_dir(&self.mount_point) {
+            return true;
+        }
+        UNION_FS_TYPES.contains(&self.fs_type.as_ref())
+            && self
+                .option_value("upperdir")
+                .is_some_and(|upperdir| in_container_dir(Path::new(upperdir)))
+    }
+    /// tell whether the mount is a virtual filesystem exposing kernel data,
+    /// based on the `nodev` flag when it's known
+    fn is_pseudo(&self) -> bool {
+        let fs_type = self.fs_base_type();
+        match self.nodev {
+            Some(nodev) => {
+                nodev
+                    && !UNION_FS_TYPES.contains(&fs_type)
+                    && !NODEV_STORAGE_FS_TYPES.contains(&fs_type)
+            }
+            None => PSEUDO_FS_TYPES.contains(&self.fs_type.as_ref()),
+        }
+    }
+    fn is_system(&self) -> bool {
+        let mount_point = &self.mount_point;
+        SYSTEM_MOUNT_POINTS
+            .iter()
+            .any(|mp| mount_point.starts_with(mp))
+            || (mount_point.starts_with("/System/Volumes")
+                && mount_point != Path::new("/System/Volumes/Data"))
+    }
+}
+
+impl Mount {
+    /// Classify the mount, based on the type of filesystem, the
+    /// device and the mount point
+    pub fn kind(&self) -> MountKind {
+        let info = &self.info;
+        let disk = self.disk.as_ref();
+        if self.is_remote() {
+            MountKind::Remote
+        } else if MEMORY_FS_TYPES.contains(&info.fs_type.as_ref()) || disk.is_some_and(|d| d.ram) {
+            MountKind::Memory
+        } else if info.is_container_layer() {
+            MountKind::ContainerLayer
+        } else if self.is_loop() {
+            MountKind::Loop
+        } else if info.is_pseudo() {
+            MountKind::Pseudo
+        } else if info.is_system() {
+            MountKind::System
+        } else {
+            MountKind::Storage
+        }
+    }
+    fn is_loop(&self) -> bool {
+        self.disk.as_ref().is_some_and(Disk::is_file_backed) || self.info.fs_type == "squashfs"
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_mount_kind() {
+    let mount = |line: &str, nodev: Option<bool>, disk: Option<&str>| {
+        let mut mount = Mount::from_mountinfo_line(line);
+        mount.info.nodev = nodev;
+        mount.disk = disk.map(|name| Disk {
+            ram: name.starts_with("zram"),
+            ..Disk::with_name(name)
+        });
+        mount
+    };
+    let kind =
+        |line: &str, nodev: Option<bool>, disk: Option<&str>| mount(line, nodev, disk).kind();
+    assert_eq!(
+        kind(
+            "25 1 8:1 / / rw - ext4 /dev/sda1 rw",
+            Some(false),
+            Some("sda")
+        ),
+        MountKind::Storage,
+    );
+    assert_eq!(
+        kind(
+            "26 25 8:2 / /boot/efi rw - vfat /dev/sda2 rw",
+            Some(false),
+            Some("sda")
+        ),
+        MountKind::System,
+    );
+    assert_eq!(
+        kind("27 25 0:5 / /proc rw - proc proc rw", Some(true), None),
+        MountKind::Pseudo,
+    );
+    // a type missing from the fallback list, known by its nodev flag
+    assert_eq!(
+        kind(
+            "28 25 0:6 / /sys/fs/foo rw - foofs foofs rw",
+            Some(true),
+            None
+        ),
+        MountKind::Pseudo,
+    );
+    assert_eq!(
+        kind("29 25 0:7 / /sys rw - sysfs sysfs rw", None, None),
+        MountKind::Pseudo,
+    );
+    assert_eq!(
+        kind("30 25 0:8 / /run rw - tmpfs tmpfs rw", Some(true), None),
+        MountKind::Memory,
+    );
+    assert_eq!(
+        kind(
+            "31 25 252:0 / /var/cache rw - ext4 /dev/zram0 rw",
+            Some(false),
+            Some("zram0")
+        ),
+        MountKind::Memory,
+    );
+    assert_eq!(
+        kind(
+            "32 25 0:9 / /var/lib/docker/overlay2/abc/merged rw - overlay overlay rw,lowerdir=/a,upperdir=/var/lib/docker/overlay2/abc/diff,workdir=/w",
+            Some(true),
+            None,
+        ),
+        MountKind::ContainerLayer,
+    );
+    assert_eq!(
+        kind(
+            "33 25 0:10 / /mnt/merged rw - overlay overlay rw,lowerdir=/a,upperdir=/b,workdir=/w",
+            Some(true),
+            None,
+        ),
+        MountKind::Storage,
+    );
+    assert_eq!(
+        kind(
+            "34 25 7:0 / /snap/core/1 ro - squashfs /dev/loop0 ro",
+            Some(false),
+            Some("loop0")
+        ),
+        MountKind::Loop,
+    );
+    assert_eq!(
+        kind(
+            "35 25 0:11 / /mnt/nas rw - nfs4 nas:/export rw",
+            Some(true),
+            None
+        ),
+        MountKind::Remote,
+    );
+    // filesystems without device which store user data
+    assert_eq!(
+        kind("37 25 0:45 / /tank rw - zfs tank rw", Some(true), None),
+        MountKind::Storage,
+    );
+    assert_eq!(
+        kind(
+            "38 25 0:46 / /mnt/host rw - virtiofs host rw",
+            Some(true),
+            None
+        ),
+        MountKind::Storage,
+    );
+    assert_eq!(
+        kind(
+            "39 25 0:47 / /mnt/shared rw - 9p shared rw",
+            Some(true),
+            None
+        ),
+        MountKind::Storage,
+    );
+    assert_eq!(
+        kind(
+            "36 25 0:12 / /mnt/vault rw - fuse.gocryptfs /home/me/vault rw",
+            Some(true),
+            None
+        ),
+        MountKind::Storage,
+    );
+}
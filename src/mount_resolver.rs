@@ -0,0 +1,158 @@
+use {
+    crate::*,
+    std::{
+        collections::HashMap,
+        fs,
+        path::{
+            Path,
+            PathBuf,
+        },
+    },
+};
+
+/// A resolver of the mounts containing paths, built once from a list
+/// of mounts, then able to resolve many paths efficiently.
+///
+/// Paths are canonicalized, so that symlinks are followed, then the
+/// mount is searched among the non shadowed mounts whose mount point is a
+/// prefix of the path, preferring the deepest one with the device id of
+/// the path. When no mount has this device id (eg for a btrfs subvolume
+/// which isn't mounted by itself), the deepest one is returned.
+#[derive(Debug, Clone)]
+pub struct MountResolver<'m> {
+    mounts: &'m [Mount],
+    /// indices of the visible mounts, by mount point, in listing order
+    by_mount_point: HashMap<&'m Path, Vec<usize>>,
+}
+
+impl<'m> MountResolver<'m> {
+    pub fn new(mounts: &'m [Mount]) -> Self {
+        let mut by_mount_point: HashMap<&Path, Vec<usize>> = HashMap::new();
+        for (idx, mount) in mounts.iter().enumerate() {
+            if !mount.is_shadowed() {
+                by_mount_point
+                    .entry(mount.info.mount_point.as_path())
+                    .or_default()
+                    .push(idx);
+            }
+        }
+        Self {
+            mounts,
+            by_mount_point,
+        }
+    }
+    /// Return the mount containing the given path, which must exist
+    pub fn resolve(
+        &self,
+        path: &Path,
+    ) -> Result<Option<&'m Mount>, Error> {
+        let path = canonicalize(path)?;
+        let dev = DeviceId::of_path(&path)?;
+        Ok(self.resolve_canonical(&path, dev))
+    }
+    /// Return the mount containing the given path, which must be
+    /// canonical and on the device of the given id
+    pub fn resolve_canonical(
+        &self,
+        path: &Path,
+        dev: DeviceId,
+    ) -> Option<&'m Mount> {
+        self.find(path, Some(dev))
+    }
+    /// Return the mount containing the given canonical path, preferring
+    /// the ones with the given device id, if any
+    pub(crate) fn find(
+        &self,
+        path: &Path,
+        dev: Option<DeviceId>,
+    ) -> Option<&'m Mount> {
+        let mut deepest = None;
+        for ancestor in path.ancestors() {
+            let Some(indices) = self.by_mount_point.get(ancestor) else {
+                continue;
+            };
+            // when several mounts are stacked, the last one is on top
+            for &idx in indices.iter().rev() {
+                let mount = &self.mounts[idx];
+                if Some(mount.info.dev) == dev {
+                    return Some(mount);
+                }
+                deepest.get_or_insert(mount);
+            }
+        }
+        deepest
+    }
+}
+
+/// Return the mount containing the given path, which must exist
+///
+/// To resolve many paths, use a `MountResolver`.
+pub fn mount_for_path<'m>(
+    mounts: &'m [Mount],
+    path: &Path,
+) -> Result<Option<&'m Mount>, Error> {
+    MountResolver::new(mounts).resolve(path)
+}
+
+/// Return the mounts containing the given paths, in the same order
+pub fn mounts_for_paths<'m, P: AsRef<Path>>(
+    mounts: &'m [Mount],
+    paths: &[P],
+) -> Vec<Result<Option<&'m Mount>, Error>> {
+    let resolver = MountResolver::new(mounts);
+    paths
+        .iter()
+        .map(|path| resolver.resolve(path.as_ref()))
+        .collect()
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, Error> {
+    let canonical = fs::canonicalize(path).map_err(|e| Error::CantReadFileMetadata {
+        source: e,
+        path: path.to_path_buf(),
+    })?;
+    // on windows, the canonical path is in the verbatim form (eg `\\?\C:\`)
+    // which doesn't share the prefix of the mount points (eg `C:\`)
+    #[cfg(windows)]
+    if let Some(stripped) = canonical
+        .to_str()
+        .and_then(|s| s.strip_prefix(r"\\?\"))
+        .filter(|s| s.as_bytes().get(1) == Some(&b':'))
+    {
+        return Ok(PathBuf::from(stripped));
+    }
+    Ok(canonical)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_resolve_canonical() {
+    let mount = Mount::from_mountinfo_line;
+    let mut mounts = vec![
+        mount("25 1 0:30 /@ / rw - btrfs /dev/sda1 rw,subvol=/@"),
+        mount("40 25 0:30 /@home /home rw - btrfs /dev/sda1 rw,subvol=/@home"),
+        mount("41 25 8:2 / /mnt/a rw - ext4 /dev/sda2 rw"),
+        mount("42 41 8:3 / /mnt/a/b rw - ext4 /dev/sda3 rw"),
+        mount("43 25 8:4 / /mnt/a rw - ext4 /dev/sda4 rw"),
+    ];
+    let dev = |s: &str| s.parse::<DeviceId>().unwrap();
+    let resolve = |mounts: &[Mount], path: &str, d: &str| {
+        MountResolver::new(mounts)
+            .resolve_canonical(Path::new(path), dev(d))
+            .and_then(|m| m.info.id)
+    };
+    // btrfs subvolumes share the device id in mountinfo
+    assert_eq!(resolve(&mounts, "/home/me/file", "0:30"), Some(40));
+    assert_eq!(resolve(&mounts, "/etc/fstab", "0:30"), Some(25));
+    // a nested subvolume which isn't mounted has its own device id
+    assert_eq!(resolve(&mounts, "/home/me/.snapshots/1", "0:55"), Some(40));
+    // without shadowing info, the device id tells /mnt/a/b is covered
+    assert_eq!(resolve(&mounts, "/mnt/a/b/file", "8:4"), Some(43));
+    let mut infos: Vec<MountInfo> = mounts.iter().map(|m| m.info.clone()).collect();
+    crate::shadowing::compute_shadowing(&mut infos);
+    for (mount, info) in mounts.iter_mut().zip(infos) {
+        mount.info = info;
+    }
+    assert!(mounts[3].is_shadowed());
+    assert_eq!(resolve(&mounts, "/mnt/a/b/file", "8:4"), Some(43));
+}
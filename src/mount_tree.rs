@@ -0,0 +1,262 @@
+use {
+    crate::*,
+    std::{
+        collections::HashMap,
+        path::Path,
+    },
+};
+
+/// The tree of mounts, built from the `id` and `parent` of their
+/// mount infos.
+///
+/// Mounts without id, or whose parent isn't in the list (for example
+/// because it's outside of the current mount namespace), are roots.
+/// On platforms where mounts have no id, all mounts are roots.
+#[derive(Debug, Clone)]
+pub struct MountTree {
+    mounts: Vec<Mount>,
+    index: HashMap<MountId, usize>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    roots: Vec<usize>,
+    /// (depth, mount index) in walk order
+    walk: Vec<(usize, usize)>,
+}
+
+impl MountTree {
+    /// Build the tree from a list of mounts, as returned by `read_mounts`
+    pub fn new(mounts: Vec<Mount>) -> Self {
+        let mut index = HashMap::new();
+        for (idx, mount) in mounts.iter().enumerate() {
+            if let Some(id) = mount.info.id {
+                index.insert(id, idx);
+            }
+        }
+        let parents: Vec<Option<usize>> = mounts
+            .iter()
+            .enumerate()
+            .map(|(idx, mount)| {
+                mount
+                    .info
+                    .parent
+                    .and_then(|parent| index.get(&parent).copied())
+                    .filter(|&parent_idx| parent_idx != idx)
+            })
+            .collect();
+        let mut children = vec![Vec::new(); mounts.len()];
+        let mut roots = Vec::new();
+        for (idx, parent) in parents.iter().enumerate() {
+            match parent {
+                Some(parent_idx) => children[*parent_idx].push(idx),
+                None => roots.push(idx),
+            }
+        }
+        // children and roots are sorted by mount point then id, so that
+        // the walk doesn't depend on the order of the mountinfo lines
+        let sort_key = |&idx: &usize| {
+            let info = &mounts[idx].info;
+            (info.mount_point.clone(), info.id)
+        };
+        roots.sort_by_cached_key(sort_key);
+        for list in &mut children {
+            list.sort_by_cached_key(sort_key);
+        }
+        let mut tree = Self {
+            mounts,
+            index,
+            parents,
+            children,
+            roots,
+            walk: Vec::new(),
+        };
+        tree.walk = tree.compute_walk();
+        tree
+    }
+    fn compute_walk(&self) -> Vec<(usize, usize)> {
+        let mut walk = Vec::with_capacity(self.mounts.len());
+        let mut visited = vec![false; self.mounts.len()];
+        let mut stack: Vec<(usize, usize)> = self.roots.iter().rev().map(|&idx| (0, idx)).collect();
+        while let Some((depth, idx)) = stack.pop() {
+            if visited[idx] {
+                continue; // a cycle, which shouldn't happen with sane data
+            }
+            visited[idx] = true;
+            walk.push((depth, idx));
+            for &child in self.children[idx].iter().rev() {
+                stack.push((depth + 1, child));
+            }
+        }
+        walk
+    }
+    /// Return all mounts, in the order they were given
+    pub fn mounts(&self) -> &[Mount] {
+        &self.mounts
+    }
+    /// Return the mount with the given id
+    pub fn get(
+        &self,
+        id: MountId,
+    ) -> Option<&Mount> {
+        self.index.get(&id).map(|&idx| &self.mounts[idx])
+    }
+    /// Return the mounts having no parent in the tree
+    pub fn roots(&self) -> impl Iterator<Item = &Mount> {
+        self.roots.iter().map(|&idx| &self.mounts[idx])
+    }
+    /// Return the parent of the mount with the given id
+    pub fn parent(
+        &self,
+        id: MountId,
+    ) -> Option<&Mount> {
+        let idx = *self.index.get(&id)?;
+        self.parents[idx].map(|parent_idx| &self.mounts[parent_idx])
+    }
+    /// Return the direct children of the mount with the given id,
+    /// sorted by mount point
+    pub fn children(
+        &self,
+        id: MountId,
+    ) -> impl Iterator<Item = &Mount> {
+        self.index
+            .get(&id)
+            .map(|&idx| self.children[idx].as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|&idx| &self.mounts[idx])
+    }
+    /// Return the ancestors of the mount with the given id, starting
+    /// with its parent and ending with a root
+    pub fn ancestors(
+        &self,
+        id: MountId,
+    ) -> impl Iterator<Item = &Mount> {
+        let mut current = self.index.get(&id).copied();
+        let mut remaining = self.mounts.len(); // protection against cycles
+        std::iter::from_fn(move || {
+            let parent_idx = self.parents[current?]?;
+            if remaining == 0 {
+                return None;
+            }
+            remaining -= 1;
+            current = Some(parent_idx);
+            Some(&self.mounts[parent_idx])
+        })
+    }
+    /// Return the depth of the mount with the given id, 0 for a root
+    pub fn depth(
+        &self,
+        id: MountId,
+    ) -> Option<usize> {
+        self.index.get(&id)?;
+        Some(self.ancestors(id).count())
+    }
+    /// Return the mount containing the given absolute path, that is the
+    /// one with the deepest mount point among the path and its ancestors.
+    ///
+    /// Paths are compared as given, without resolving symlinks.
+    /// When several mounts are stacked on the same mount point, the
+    /// last mounted one (the visible one) is returned.
+    pub fn find_by_path(
+        &self,
+        path: &Path,
+    ) -> Option<&Mount> {
+        path.ancestors()
+            .find_map(|dir| self.mounts.iter().rev().find(|m| m.info.mount_point == dir))
+    }
+    /// Return the subtree of the mount with the given id, starting with
+    /// this mount, with the depth of each mount relative to it
+    pub fn subtree(
+        &self,
+        id: MountId,
+    ) -> impl Iterator<Item = (usize, &Mount)> {
+        let start = self
+            .index
+            .get(&id)
+            .and_then(|&idx| self.walk.iter().position(|&(_, i)| i == idx));
+        let (base_depth, entries) = match start {
+            Some(pos) => (self.walk[pos].0, &self.walk[pos..]),
+            None => (0, &self.walk[..0]),
+        };
+        entries
+            .iter()
+            .enumerate()
+            .take_while(move |&(i, &(depth, _))| i == 0 || depth > base_depth)
+            .map(move |(_, &(depth, idx))| (depth - base_depth, &self.mounts[idx]))
+    }
+    /// Return the subtree of the mount containing the given path
+    /// (see `find_by_path`), starting with this mount
+    pub fn subtree_at(
+        &self,
+        path: &Path,
+    ) -> impl Iterator<Item = (usize, &Mount)> {
+        let id = self.find_by_path(path).and_then(|m| m.info.id);
+        id.into_iter().flat_map(|id| self.subtree(id))
+    }
+    /// Return all mounts with their depth, in a stable depth-first order:
+    /// roots then children are sorted by mount point, then by id
+    pub fn walk(&self) -> impl Iterator<Item = (usize, &Mount)> {
+        self.walk
+            .iter()
+            .map(|&(depth, idx)| (depth, &self.mounts[idx]))
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_mount_tree() {
+    use std::path::PathBuf;
+    let lines = [
+        "25 1 8:1 / / rw - ext4 /dev/sda1 rw",
+        "40 25 0:5 / /dev rw - devtmpfs devtmpfs rw",
+        "30 25 0:20 / /sys rw - sysfs sysfs rw",
+        "41 40 0:21 / /dev/shm rw - tmpfs tmpfs rw",
+        "31 30 0:22 / /sys/fs/cgroup rw - cgroup2 cgroup2 rw",
+        "50 25 8:2 / /home rw - ext4 /dev/sda2 rw",
+    ];
+    let mounts = lines
+        .iter()
+        .map(|line| Mount::from_mountinfo_line(line))
+        .collect();
+    let tree = MountTree::new(mounts);
+    let walk: Vec<(usize, &str)> = tree
+        .walk()
+        .map(|(depth, m)| (depth, m.info.mount_point.to_str().unwrap()))
+        .collect();
+    assert_eq!(
+        walk,
+        vec![
+            (0, "/"),
+            (1, "/dev"),
+            (2, "/dev/shm"),
+            (1, "/home"),
+            (1, "/sys"),
+            (2, "/sys/fs/cgroup"),
+        ]
+    );
+    assert_eq!(tree.roots().count(), 1);
+    assert_eq!(tree.depth(31), Some(2));
+    assert_eq!(tree.depth(99), None);
+    let ancestors: Vec<MountId> = tree.ancestors(31).filter_map(|m| m.info.id).collect();
+    assert_eq!(ancestors, vec![30, 25]);
+    let children: Vec<MountId> = tree.children(25).filter_map(|m| m.info.id).collect();
+    assert_eq!(children, vec![40, 50, 30]);
+    let subtree: Vec<(usize, MountId)> = tree
+        .subtree_at(&PathBuf::from("/sys"))
+        .map(|(depth, m)| (depth, m.info.id.unwrap()))
+        .collect();
+    assert_eq!(subtree, vec![(0, 30), (1, 31)]);
+    let subtree: Vec<(usize, MountId)> = tree
+        .subtree_at(&PathBuf::from("/sys/fs/cgroup/user.slice"))
+        .map(|(depth, m)| (depth, m.info.id.unwrap()))
+        .collect();
+    assert_eq!(subtree, vec![(0, 31)]);
+    let find = |path: &str| {
+        tree.find_by_path(&PathBuf::from(path))
+            .and_then(|m| m.info.id)
+    };
+    assert_eq!(find("/sys/fs"), Some(30));
+    assert_eq!(find("/home/user/file"), Some(50));
+    assert_eq!(find("/nope"), Some(25));
+    assert_eq!(find("relative"), None);
+    assert_eq!(tree.subtree_at(&PathBuf::from("/nope")).count(), 6);
+}
@@ -30,14 +30,36 @@ pub type MountId = u32;
 pub struct MountInfo {
     pub id: Option<MountId>,
     pub parent: Option<MountId>,
+    /// the 64 bits id of the mount, never reused
+    /// (only filled with the statmount strategy)
+    pub unique_id: Option<u64>,
+    /// the device id of the superblock (`sb_dev` for statmount), which
+    /// identifies the filesystem and is shared by all its mounts
     pub dev: DeviceId,
     pub root: PathBuf,
     pub mount_point: PathBuf,
-    pub options: Vec<MountOption>,
+    /// options of this mount point (eg "ro", "nosuid", "relatime")
+    pub mount_options: Vec<MountOption>,
+    /// options of the filesystem, shared by all its mount points,
+    /// (eg "rw", "compress=zstd:3"), only filled on linux
+    pub super_options: Vec<MountOption>,
     pub fs: String, // rename into "node" ?
     pub fs_type: String,
+    /// whether the type of filesystem needs no block device, as declared
+    /// in /proc/filesystems (only filled on linux)
+    pub nodev: Option<bool>,
     /// whether it's a bound mount (usually mirroring part of another device)
     pub bound: bool,
+    /// why it's a bound mount (only computed on linux, from mountinfo)
+    pub bound_reason: Option<BoundReason>,
+    /// for a bind mount, its origin (only computed on linux, from mountinfo)
+    pub bind_source: Option<BindSource>,
+    /// the propagation type and peer groups (only filled on linux)
+    pub propagation: Propagation,
+    /// whether it's covered by another mount, on the same mount point or
+    /// on a parent one, so that it's not reachable through its mount point
+    /// (only computed on linux)
+    pub shadowed: bool,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -56,6 +78,26 @@ impl MountOption {
             value: value.map(|s| s.into()),
         }
     }
+    /// parse a comma separated list of options, like "rw,noatime,compress=zstd:3",
+    /// whose names and values may hold ascii-octal escape sequences
+    /// (a `,` or `=` inside a value being escaped, as the kernel does)
+    #[cfg(target_os = "linux")]
+    pub(crate) fn parse_list<B: AsRef<[u8]>>(s: B) -> Vec<Self> {
+        s.as_ref()
+            .split(|&b| b == b',')
+            .filter(|option| !option.is_empty())
+            .map(|option| {
+                let (name, value) = match option.iter().position(|&b| b == b'=') {
+                    Some(idx) => (&option[..idx], Some(&option[idx + 1..])),
+                    None => (option, None),
+                };
+                MountOption {
+                    name: sys::decode_string(name),
+                    value: value.map(sys::decode_string),
+                }
+            })
+            .collect()
+    }
 }
 
 impl MountInfo {
@@ -72,38 +114,39 @@ impl MountInfo {
     /// Heuristics copied from <https://github.com/coreutils/gnulib/blob/master/lib/mountlist.c>
     #[cfg(unix)]
     pub fn is_remote(&self) -> bool {
-        self.fs.contains(':')
+        // the fs of mergerfs is the list of its branches
+        (self.fs.contains(':') && self.fs_type != "fuse.mergerfs")
             || (self.fs.starts_with("//")
                 && ["cifs", "smb3", "smbfs"].contains(&self.fs_type.as_ref()))
             || REMOTE_ONLY_FS_TYPES.contains(&self.fs_type.as_ref())
             || self.fs == "-hosts"
     }
+    /// return the options of the mount point followed by the options of
+    /// the filesystem which aren't overridden at the mount point level.
+    ///
+    /// The `rw` super option is skipped as it's not relevant when the
+    /// mount point itself is read-only.
+    pub fn options(&self) -> impl Iterator<Item = &MountOption> {
+        let super_options = self.super_options.iter().filter(|so| {
+            so.name != "rw" && !self.mount_options.iter().any(|mo| mo.name == so.name)
+        });
+        self.mount_options.iter().chain(super_options)
+    }
     /// return a string like "rw,noatime,compress=zstd:3,space_cache=v2,subvolid=256"
     /// (as in /proc/mountinfo)
     ///
     /// Some options may be skipped as they're less relevant (but you may still find them
-    /// in the options vec)
+    /// in the options vecs)
     pub fn options_string(&self) -> String {
-        let mut s = String::new();
-        let mut first = true;
-        for option in &self.options {
-            if OPTIONS_NOT_IN_OPTIONS_STRING
-                .iter()
-                .any(|s| s == &option.name)
-            {
-                continue;
-            }
-            if !first {
-                s.push(',');
-            }
-            s.push_str(&option.name);
-            if let Some(value) = &option.value {
-                s.push('=');
-                s.push_str(value);
-            }
-            first = false;
-        }
-        s
+        options_list_string(
+            self.options()
+                .filter(|option| !OPTIONS_NOT_IN_OPTIONS_STRING.contains(&option.name.as_str())),
+        )
+    }
+    /// return a typed view of the options, with the well known
+    /// options interpreted (eg access mode, atime policy, btrfs subvolume)
+    pub fn typed_options(&self) -> TypedMountOptions {
+        TypedMountOptions::new(&self.fs_type, self.options())
     }
     /// tell whether the option (eg "compress", "rw", "noatime") is present
     /// among options
@@ -111,7 +154,7 @@ impl MountInfo {
         &self,
         name: &str,
     ) -> bool {
-        for option in &self.options {
+        for option in self.options() {
             if option.name == name {
                 return true;
             }
@@ -123,11 +166,116 @@ impl MountInfo {
         &self,
         name: &str,
     ) -> Option<&str> {
-        for option in &self.options {
+        for option in self.options() {
             if option.name == name {
                 return option.value.as_deref();
             }
         }
         None
     }
+    /// return a line in the format of /proc/self/mountinfo, with special
+    /// chars of paths escaped.
+    ///
+    /// Missing ids are written as 0.
+    pub fn to_mountinfo_line(&self) -> String {
+        let mut line = format!(
+            "{} {} {} {} {} {}",
+            self.id.unwrap_or(0),
+            self.parent.unwrap_or(0),
+            self.dev,
+            sys::encode_path(&self.root),
+            sys::encode_path(&self.mount_point),
+            encode_options_list(&self.mount_options),
+        );
+        let propagation = &self.propagation;
+        if let Some(group) = propagation.shared {
+            line.push_str(&format!(" shared:{group}"));
+        }
+        if let Some(group) = propagation.master {
+            line.push_str(&format!(" master:{group}"));
+        }
+        if let Some(group) = propagation.propagate_from {
+            line.push_str(&format!(" propagate_from:{group}"));
+        }
+        if propagation.unbindable {
+            line.push_str(" unbindable");
+        }
+        line.push_str(&format!(
+            " - {} {}",
+            sys::encode_string(&self.fs_type),
+            sys::encode_string(&self.fs),
+        ));
+        if !self.super_options.is_empty() {
+            line.push(' ');
+            line.push_str(&encode_options_list(&self.super_options));
+        }
+        line
+    }
+    /// tell whether the mount is read-only, either because the mount
+    /// point is read-only or because the whole filesystem is
+    pub fn is_read_only(&self) -> bool {
+        self.mount_options.iter().any(|o| o.name == "ro")
+            || self.super_options.iter().any(|o| o.name == "ro")
+    }
+    /// tell whether the filesystem itself (its superblock) is read-only,
+    /// as opposed to only this mount point (eg a read-only bind mount)
+    pub fn is_fs_read_only(&self) -> bool {
+        self.super_options.iter().any(|o| o.name == "ro")
+    }
+    /// Tell whether the two infos describe the same mount, possibly read
+    /// at different times, its mount point or options having changed.
+    ///
+    /// Mounts with an id are compared on their id and device, as an id
+    /// may be reused after an unmount. Other ones are compared on their
+    /// device, root and mount point.
+    pub fn is_same_mount(
+        &self,
+        other: &MountInfo,
+    ) -> bool {
+        match (self.id, other.id) {
+            (Some(a_id), Some(b_id)) => a_id == b_id && self.dev == other.dev,
+            _ => {
+                self.dev == other.dev
+                    && self.root == other.root
+                    && self.mount_point == other.mount_point
+            }
+        }
+    }
+}
+
+/// return the options as a comma separated list, like "rw,noatime,compress=zstd:3"
+pub(crate) fn options_list_string<'o, I: IntoIterator<Item = &'o MountOption>>(
+    options: I
+) -> String {
+    let mut s = String::new();
+    for option in options {
+        if !s.is_empty() {
+            s.push(',');
+        }
+        s.push_str(&option.name);
+        if let Some(value) = &option.value {
+            s.push('=');
+            s.push_str(value);
+        }
+    }
+    s
+}
+
+/// return the options as a comma separated list, as in mountinfo or fstab,
+/// special chars of names and values being escaped
+pub(crate) fn encode_options_list<'o, I: IntoIterator<Item = &'o MountOption>>(
+    options: I
+) -> String {
+    let mut s = String::new();
+    for option in options {
+        if !s.is_empty() {
+            s.push(',');
+        }
+        s.push_str(&sys::encode_option_name(&option.name));
+        if let Some(value) = &option.value {
+            s.push('=');
+            s.push_str(&sys::encode_option_value(value));
+        }
+    }
+    s
 }
@@ -0,0 +1,187 @@
+use crate::*;
+
+/// Differences between two snapshots of the mounts
+#[derive(Debug, Clone)]
+pub struct MountsDiff<'m> {
+    /// mounts only found in the new snapshot
+    pub added: Vec<&'m Mount>,
+    /// mounts only found in the old snapshot
+    pub removed: Vec<&'m Mount>,
+    /// mounts found in both snapshots, with changes
+    pub changed: Vec<MountChange<'m>>,
+}
+
+/// The changes of a mount between two snapshots
+#[derive(Debug, Clone)]
+pub struct MountChange<'m> {
+    pub old: &'m Mount,
+    pub new: &'m Mount,
+    pub mount_point_changed: bool,
+    /// options of the mount point or of the filesystem changed
+    pub options_changed: bool,
+    /// the backing device (device id or fs) changed
+    pub device_changed: bool,
+    pub label_changed: bool,
+    /// the filesystem UUID or the partition UUID changed
+    pub uuid_changed: bool,
+    /// evolution of the space, when stats are available in both snapshots
+    pub space_delta: Option<SpaceDelta>,
+}
+
+/// Evolution of the space of a mount, in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpaceDelta {
+    pub size: i64,
+    pub used: i64,
+    pub available: i64,
+}
+
+impl MountChange<'_> {
+    fn new<'m>(
+        old: &'m Mount,
+        new: &'m Mount,
+    ) -> MountChange<'m> {
+        let space_delta = old.stats().zip(new.stats()).map(|(old, new)| SpaceDelta {
+            size: new.size() as i64 - old.size() as i64,
+            used: new.used() as i64 - old.used() as i64,
+            available: new.available() as i64 - old.available() as i64,
+        });
+        MountChange {
+            old,
+            new,
+            mount_point_changed: old.info.mount_point != new.info.mount_point,
+            options_changed: old.info.mount_options != new.info.mount_options
+                || old.info.super_options != new.info.super_options,
+            device_changed: old.info.dev != new.info.dev || old.info.fs != new.info.fs,
+            label_changed: old.fs_label != new.fs_label,
+            uuid_changed: old.uuid != new.uuid || old.part_uuid != new.part_uuid,
+            space_delta,
+        }
+    }
+    /// tell whether anything changed, including the space
+    pub fn has_changes(&self) -> bool {
+        self.mount_point_changed
+            || self.options_changed
+            || self.device_changed
+            || self.label_changed
+            || self.uuid_changed
+            || self
+                .space_delta
+                .is_some_and(|delta| delta != SpaceDelta::default())
+    }
+}
+
+/// Compute the differences between two snapshots of the mounts.
+///
+/// Mounts are first matched by mount id, provided they also share either
+/// their mount point or their device (ids are only stable during a boot
+/// and may be reused), then by device, root and mount point.
+pub fn diff_mounts<'m>(
+    old_mounts: &'m [Mount],
+    new_mounts: &'m [Mount],
+) -> MountsDiff<'m> {
+    let mut new_matched = vec![false; new_mounts.len()];
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    let mut old_unmatched = Vec::new();
+    for (old_idx, old) in old_mounts.iter().enumerate() {
+        let old_info = &old.info;
+        let found = new_mounts.iter().enumerate().position(|(new_idx, new)| {
+            let new_info = &new.info;
+            !new_matched[new_idx]
+                && old_info.id.is_some()
+                && old_info.id == new_info.id
+                && (old_info.mount_point == new_info.mount_point || old_info.dev == new_info.dev)
+        });
+        match found {
+            Some(new_idx) => {
+                new_matched[new_idx] = true;
+                pairs.push((old_idx, new_idx));
+            }
+            None => old_unmatched.push(old_idx),
+        }
+    }
+    let mut removed = Vec::new();
+    for old_idx in old_unmatched {
+        let old_info = &old_mounts[old_idx].info;
+        let found = new_mounts.iter().enumerate().position(|(new_idx, new)| {
+            let new_info = &new.info;
+            !new_matched[new_idx]
+                && old_info.dev == new_info.dev
+                && old_info.root == new_info.root
+                && old_info.mount_point == new_info.mount_point
+        });
+        match found {
+            Some(new_idx) => {
+                new_matched[new_idx] = true;
+                pairs.push((old_idx, new_idx));
+            }
+            None => removed.push(&old_mounts[old_idx]),
+        }
+    }
+    pairs.sort_unstable();
+    let changed = pairs
+        .into_iter()
+        .map(|(old_idx, new_idx)| MountChange::new(&old_mounts[old_idx], &new_mounts[new_idx]))
+        .filter(MountChange::has_changes)
+        .collect();
+    let added = new_mounts
+        .iter()
+        .zip(new_matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(mount, _)| mount)
+        .collect();
+    MountsDiff {
+        added,
+        removed,
+        changed,
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_diff_mounts() {
+    let mount = |line: &str, bavail: u64| Mount {
+        stats: Ok(Stats {
+            bsize: 1024,
+            blocks: 1000,
+            bused: 1000 - bavail,
+            bfree: bavail,
+            bavail,
+            inodes: None,
+        }),
+        ..Mount::from_mountinfo_line(line)
+    };
+    let old_mounts = vec![
+        mount("25 1 8:1 / / rw - ext4 /dev/sda1 rw", 500),
+        mount("40 25 8:2 / /home rw - ext4 /dev/sda2 rw", 100),
+        mount("41 25 8:3 / /opt rw - ext4 /dev/sda3 rw", 100),
+        mount("42 25 8:4 / /var rw - ext4 /dev/sda4 rw", 100),
+    ];
+    let mut new_mounts = vec![
+        // after a reboot, ids changed
+        mount("22 1 8:1 / / rw - ext4 /dev/sda1 rw", 400),
+        mount("40 22 8:2 / /home rw,noatime - ext4 /dev/sda2 rw", 100),
+        mount("41 22 8:5 / /opt rw - ext4 /dev/sda5 rw", 100),
+        mount("43 22 8:6 / /srv rw - ext4 /dev/sda6 rw", 100),
+    ];
+    new_mounts[1].fs_label = Some("home".into());
+    let diff = diff_mounts(&old_mounts, &new_mounts);
+    assert_eq!(diff.removed.len(), 1);
+    assert_eq!(diff.removed[0].info.fs, "/dev/sda4");
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].info.fs, "/dev/sda6");
+    assert_eq!(diff.changed.len(), 3);
+    assert!(diff.changed[0].old.info.mount_point.as_os_str() == "/");
+    assert_eq!(
+        diff.changed[0].space_delta,
+        Some(SpaceDelta {
+            size: 0,
+            used: 100 * 1024,
+            available: -100 * 1024,
+        })
+    );
+    assert!(diff.changed[1].options_changed);
+    assert!(diff.changed[1].label_changed);
+    assert!(!diff.changed[1].device_changed);
+    assert!(diff.changed[2].device_changed);
+}
@@ -0,0 +1,111 @@
+use crate::*;
+
+/// An id of a peer group, as found in the `shared:X`, `master:X`
+/// and `propagate_from:X` optional fields of mountinfo
+pub type PeerGroupId = u32;
+
+/// The propagation state of a mount, as described in
+/// <https://www.kernel.org/doc/Documentation/filesystems/sharedsubtree.txt>
+///
+/// As in the kernel, shared, slave, private and unbindable are distinct
+/// propagation types: a mount with no peer group, no master and which
+/// isn't unbindable is private (the default).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Propagation {
+    /// the peer group the mount shares events with (`shared:X`)
+    pub shared: Option<PeerGroupId>,
+    /// the peer group the mount receives events from (`master:X`)
+    pub master: Option<PeerGroupId>,
+    /// the closest dominant peer group under the same root,
+    /// only present when the master isn't reachable (`propagate_from:X`)
+    pub propagate_from: Option<PeerGroupId>,
+    /// whether the mount is unbindable: like a private mount it neither
+    /// sends nor receives events, but it also can't be bound
+    pub unbindable: bool,
+}
+
+impl Propagation {
+    /// tell whether the mount sends and receives events to/from its peers
+    pub fn is_shared(&self) -> bool {
+        self.shared.is_some()
+    }
+    /// tell whether the mount receives events from a master peer group
+    pub fn is_slave(&self) -> bool {
+        self.master.is_some()
+    }
+    /// tell whether the mount neither sends nor receives events,
+    /// and isn't unbindable
+    pub fn is_private(&self) -> bool {
+        self.shared.is_none() && self.master.is_none() && !self.unbindable
+    }
+    /// Apply an optional field of mountinfo (eg "shared:27"),
+    /// ignoring unknown fields
+    #[cfg(target_os = "linux")]
+    pub(crate) fn apply_field(
+        &mut self,
+        field: &str,
+    ) {
+        if field == "unbindable" {
+            self.unbindable = true;
+            return;
+        }
+        let Some((tag, value)) = field.split_once(':') else {
+            return;
+        };
+        let Ok(group) = value.parse() else {
+            return;
+        };
+        match tag {
+            "shared" => self.shared = Some(group),
+            "master" => self.master = Some(group),
+            "propagate_from" => self.propagate_from = Some(group),
+            _ => {}
+        }
+    }
+}
+
+/// return the mounts which are members of the given peer group
+pub fn peer_group_mounts(
+    mounts: &[Mount],
+    group: PeerGroupId,
+) -> impl Iterator<Item = &Mount> {
+    mounts
+        .iter()
+        .filter(move |m| m.info.propagation.shared == Some(group))
+}
+
+/// return the mounts receiving propagation events from the given
+/// master peer group
+pub fn slave_mounts(
+    mounts: &[Mount],
+    master: PeerGroupId,
+) -> impl Iterator<Item = &Mount> {
+    mounts
+        .iter()
+        .filter(move |m| m.info.propagation.master == Some(master))
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_propagation_states() {
+    let propagation = |fields: &[&str]| {
+        let mut propagation = Propagation::default();
+        for field in fields {
+            propagation.apply_field(field);
+        }
+        propagation
+    };
+    let private = propagation(&[]);
+    assert!(private.is_private());
+    assert!(!private.unbindable);
+    let unbindable = propagation(&["unbindable"]);
+    assert!(!unbindable.is_private());
+    assert!(unbindable.unbindable);
+    let slave = propagation(&["master:3", "propagate_from:2"]);
+    assert!(slave.is_slave());
+    assert!(!slave.is_private());
+    assert_eq!(slave.propagate_from, Some(2));
+    let shared = propagation(&["shared:5"]);
+    assert!(shared.is_shared());
+    assert!(!shared.is_private());
+}
@@ -1,4 +1,5 @@
 use std::{
+    path::PathBuf,
     str::FromStr,
     time::Duration,
 };
@@ -11,13 +12,28 @@ pub enum Strategy {
     /// On mac, with this strategy, the output of the diskutil
     /// command is parsed
     Diskutil,
+    /// On linux, with this strategy, /proc/self/mountinfo is parsed
+    /// (this is the default)
+    Mountinfo,
+    /// On linux, with this strategy, /proc/self/mounts (or /etc/mtab when
+    /// not available) is parsed. Mount ids, parents and roots are then
+    /// unknown, and device ids are obtained by stating the mount points.
+    ProcMounts,
+    /// On linux 6.11+, with this strategy, the mounts are read with the
+    /// listmount and statmount syscalls, which gives the unique mount ids.
+    /// Mountinfo is parsed instead when those syscalls aren't available or
+    /// don't give everything mountinfo gives (eg the source, before 6.11),
+    /// or when a pid or a system root is given.
+    Statmount,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct ReadOptions {
     pub(crate) remote_stats: bool,
     pub(crate) strategy: Option<Strategy>,
     pub(crate) stats_timeout: Option<Duration>,
+    pub(crate) pid: Option<u32>,
+    pub(crate) system_root: Option<PathBuf>,
 }
 impl Default for ReadOptions {
     fn default() -> Self {
@@ -25,6 +41,8 @@ impl Default for ReadOptions {
             remote_stats: true,
             strategy: None,
             stats_timeout: Some(Duration::from_millis(50)),
+            pid: None,
+            system_root: None,
         }
     }
 }
@@ -52,6 +70,46 @@ impl ReadOptions {
         self.stats_timeout = v;
         self
     }
+    /// Read the mounts as seen by the process with the given pid,
+    /// for example a process running in a container (linux only).
+    ///
+    /// The mount table is then read in `/proc/<pid>/mountinfo` and the
+    /// stats are read through `/proc/<pid>/root`, which requires the
+    /// permission to inspect this process.
+    pub fn pid(
+        mut self,
+        v: u32,
+    ) -> Self {
+        self.pid = Some(v);
+        self
+    }
+    /// Read the system files (`/proc`, `/sys`, `/dev/disk`) under the given
+    /// directory instead of `/` (linux only).
+    ///
+    /// This makes it possible to analyze a captured copy of those
+    /// directories, a chroot, or the host directories mounted in a container.
+    /// Stats are read through this directory, so they're only available
+    /// when the mount points under it are the mounted filesystems.
+    pub fn system_root<P: Into<PathBuf>>(
+        mut self,
+        v: P,
+    ) -> Self {
+        self.system_root = Some(v.into());
+        self
+    }
+    /// Return the path of a system file (eg "/sys/block"), taking
+    /// the system root into account
+    #[cfg(target_os = "linux")]
+    pub(crate) fn system_path<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> PathBuf {
+        let path = path.as_ref();
+        match &self.system_root {
+            Some(root) => root.join(path.strip_prefix("/").unwrap_or(path)),
+            None => path.to_path_buf(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -62,6 +120,9 @@ impl FromStr for Strategy {
         match s {
             "iokit" => Ok(Self::Iokit),
             "diskutil" => Ok(Self::Diskutil),
+            "mountinfo" => Ok(Self::Mountinfo),
+            "procmounts" => Ok(Self::ProcMounts),
+            "statmount" => Ok(Self::Statmount),
             _ => Err(ParseStrategyError),
         }
     }
@@ -0,0 +1,107 @@
+use crate::*;
+#[cfg(target_os = "linux")]
+use std::{
+    collections::HashMap,
+    path::Path,
+};
+
+/// Set the `shadowed` flag of the mount infos, which must be given
+/// in the order of the mount table (i.e. mostly the mount order).
+///
+/// A mount is shadowed when a mount which isn't one of its ancestors
+/// is listed after it, either on the same mount point or on a parent
+/// path of its mount point, or when one of its ancestors is shadowed.
+///
+/// Mounts without id aren't considered, as their ancestry is unknown.
+#[cfg(target_os = "linux")]
+pub(crate) fn compute_shadowing(infos: &mut [MountInfo]) {
+    let mut by_id: HashMap<MountId, usize> = HashMap::new();
+    let mut by_mount_point: HashMap<&Path, Vec<usize>> = HashMap::new();
+    for (idx, info) in infos.iter().enumerate() {
+        if let Some(id) = info.id {
+            by_id.insert(id, idx);
+            by_mount_point
+                .entry(info.mount_point.as_path())
+                .or_default()
+                .push(idx);
+        }
+    }
+    let ancestors = |idx: usize| -> Vec<usize> {
+        let mut ancestors = Vec::new();
+        let mut current = idx;
+        while let Some(&parent_idx) = infos[current].parent.and_then(|p| by_id.get(&p)) {
+            if parent_idx == idx || ancestors.contains(&parent_idx) {
+                break; // cycle
+            }
+            ancestors.push(parent_idx);
+            current = parent_idx;
+        }
+        ancestors
+    };
+    // for each mount, the mounts directly covering it
+    let mut shadowers: Vec<Vec<usize>> = vec![Vec::new(); infos.len()];
+    for (idx, info) in infos.iter().enumerate() {
+        if info.id.is_none() {
+            continue;
+        }
+        let ancestors = ancestors(idx);
+        for path in info.mount_point.ancestors() {
+            if let Some(indices) = by_mount_point.get(path) {
+                shadowers[idx].extend(
+                    indices
+                        .iter()
+                        .filter(|&&other| other > idx && !ancestors.contains(&other)),
+                );
+            }
+        }
+    }
+    // the children of a shadowed mount are shadowed too, unless they're
+    // the ones covering it
+    let shadowed: Vec<bool> = (0..infos.len())
+        .map(|idx| {
+            let ancestors = ancestors(idx);
+            !shadowers[idx].is_empty()
+                || ancestors.iter().any(|&a| {
+                    shadowers[a]
+                        .iter()
+                        .any(|s| *s != idx && !ancestors.contains(s))
+                })
+        })
+        .collect();
+    for (info, shadowed) in infos.iter_mut().zip(shadowed) {
+        info.shadowed = shadowed;
+    }
+}
+
+/// Return the mounts which aren't shadowed by another mount
+pub fn visible_mounts(mounts: &[Mount]) -> impl Iterator<Item = &Mount> {
+    mounts.iter().filter(|m| !m.is_shadowed())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_compute_shadowing() {
+    use std::str::FromStr;
+    let lines = [
+        "25 1 8:1 / / rw - ext4 /dev/sda1 rw",
+        "40 25 8:2 / /mnt rw - ext4 /dev/sda2 rw",
+        "41 40 8:3 / /mnt/a rw - ext4 /dev/sda3 rw",
+        "42 41 8:4 / /mnt/a/b rw - ext4 /dev/sda4 rw",
+        "43 25 8:5 / /data rw - ext4 /dev/sda5 rw",
+        "44 43 8:6 / /data rw - ext4 /dev/sda6 rw",
+        "45 40 8:7 / /mnt/a rw - ext4 /dev/sda7 rw",
+        "46 45 8:8 / /mnt/a/c rw - ext4 /dev/sda8 rw",
+        "47 41 8:9 / /mnt/a/d rw - ext4 /dev/sda9 rw",
+    ];
+    let mut infos: Vec<MountInfo> = lines
+        .iter()
+        .map(|line| MountInfo::from_str(line).unwrap())
+        .collect();
+    compute_shadowing(&mut infos);
+    let shadowed: Vec<MountId> = infos
+        .iter()
+        .filter(|info| info.shadowed)
+        .filter_map(|info| info.id)
+        .collect();
+    assert_eq!(shadowed, vec![41, 42, 43, 47]);
+}
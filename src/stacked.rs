@@ -0,0 +1,236 @@
+use {
+    crate::*,
+    std::path::{
+        Path,
+        PathBuf,
+    },
+};
+
+/// The role of a layer in a stacked filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerRole {
+    /// a read-only layer of an overlay
+    Lower,
+    /// the writable layer of an overlay
+    Upper,
+    /// the work directory of an overlay, used internally
+    Work,
+    /// a branch of a union filesystem (eg mergerfs)
+    Branch,
+    /// the directory holding the encrypted files (eg ecryptfs, gocryptfs)
+    Cipher,
+}
+
+/// A directory on which a stacked filesystem is built
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackedLayer {
+    pub path: PathBuf,
+    pub role: LayerRole,
+    /// whether files are written in this layer
+    pub writable: bool,
+    /// id of the mount the layer lives on, when found
+    pub mount_id: Option<MountId>,
+    /// mount point of the mount the layer lives on, when found
+    pub mount_point: Option<PathBuf>,
+}
+
+/// The directories a stacked filesystem (overlayfs, fuse-overlayfs,
+/// mergerfs, ecryptfs, gocryptfs) is built on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackedSource {
+    /// layers, the uppermost first for an overlay, in the order of
+    /// the branches for a union filesystem
+    pub layers: Vec<StackedLayer>,
+}
+
+impl StackedLayer {
+    fn new<P: Into<PathBuf>>(
+        path: P,
+        role: LayerRole,
+        writable: bool,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            role,
+            writable,
+            mount_id: None,
+            mount_point: None,
+        }
+    }
+}
+
+impl StackedSource {
+    /// Build the stacked source of a mount from its type and options,
+    /// without the mounts of the layers.
+    ///
+    /// Return None if it's not a stacked filesystem, or if its layers
+    /// aren't found (fuse-overlayfs doesn't always expose its options).
+    pub fn from_mount_info(info: &MountInfo) -> Option<Self> {
+        let read_only = info.is_read_only();
+        let layers = match info.fs_type.as_str() {
+            "overlay" | "fuse.fuse-overlayfs" => {
+                let FsOptions::Overlay(overlay) = info.typed_options().fs else {
+                    return None;
+                };
+                let upper = overlay
+                    .upperdir
+                    .map(|path| StackedLayer::new(path, LayerRole::Upper, !read_only));
+                let lower = overlay
+                    .lowerdir
+                    .into_iter()
+                    .map(|path| StackedLayer::new(path, LayerRole::Lower, false));
+                let work = overlay
+                    .workdir
+                    .map(|path| StackedLayer::new(path, LayerRole::Work, false));
+                upper.into_iter().chain(lower).chain(work).collect()
+            }
+            "fuse.mergerfs" => info
+                .fs
+                .split(':')
+                .filter(|branch| branch.starts_with('/'))
+                .map(|branch| {
+                    // a branch may have a mode, eg "/mnt/disk1=RO"
+                    let (path, mode) = branch.split_once('=').unwrap_or((branch, "RW"));
+                    StackedLayer::new(path, LayerRole::Branch, !read_only && mode != "RO")
+                })
+                .collect(),
+            "ecryptfs" | "fuse.gocryptfs" if info.fs.starts_with('/') => {
+                vec![StackedLayer::new(&info.fs, LayerRole::Cipher, !read_only)]
+            }
+            _ => Vec::new(),
+        };
+        if layers.is_empty() {
+            None
+        } else {
+            Some(Self { layers })
+        }
+    }
+    /// Return the layer where the files are written, which is the one
+    /// whose free space is available
+    pub fn writable_layer(&self) -> Option<&StackedLayer> {
+        self.layers
+            .iter()
+            .find(|layer| layer.writable && layer.role != LayerRole::Work)
+    }
+}
+
+impl Mount {
+    /// Return the mount whose free space is the one available for
+    /// writing in this mount: the mount of the writable layer for a
+    /// stacked filesystem (recursively), this mount for other ones
+    pub fn space_mount<'m>(
+        &'m self,
+        mounts: &'m [Mount],
+    ) -> &'m Mount {
+        let mut current = self;
+        for _ in 0..mounts.len() {
+            let Some(layer) = current.stacked.as_ref().and_then(|s| s.writable_layer()) else {
+                break;
+            };
+            let found = mounts.iter().rev().find(|m| match layer.mount_id {
+                Some(id) => m.info.id == Some(id),
+                None => layer.mount_point.as_deref() == Some(m.info.mount_point.as_path()),
+            });
+            match found {
+                Some(mount) if !std::ptr::eq(mount, current) => current = mount,
+                _ => break,
+            }
+        }
+        current
+    }
+}
+
+/// Set the mount of the layers of the stacked mounts, the device id of
+/// a layer, when given, helping choosing among the candidate mounts
+#[cfg(target_os = "linux")]
+pub(crate) fn resolve_stacked_layers<F>(
+    mounts: &mut [Mount],
+    dev_of: F,
+) where
+    F: Fn(&Path) -> Option<DeviceId>,
+{
+    let resolver = MountResolver::new(mounts);
+    let mut resolved = Vec::new();
+    for (idx, mount) in mounts.iter().enumerate() {
+        let Some(stacked) = &mount.stacked else {
+            continue;
+        };
+        for (layer_idx, layer) in stacked.layers.iter().enumerate() {
+            if let Some(layer_mount) = resolver.find(&layer.path, dev_of(&layer.path)) {
+                resolved.push((
+                    idx,
+                    layer_idx,
+                    layer_mount.info.id,
+                    layer_mount.info.mount_point.clone(),
+                ));
+            }
+        }
+    }
+    for (idx, layer_idx, mount_id, mount_point) in resolved {
+        if let Some(stacked) = &mut mounts[idx].stacked {
+            let layer = &mut stacked.layers[layer_idx];
+            layer.mount_id = mount_id;
+            layer.mount_point = Some(mount_point);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_stacked_source() {
+    let source = |line: &str| StackedSource::from_mount_info(&line.parse().unwrap());
+    let overlay = source(
+        r"60 25 0:50 / /merged rw - overlay overlay rw,lowerdir=/l2:/l\:1,upperdir=/data/u,workdir=/data/w",
+    )
+    .unwrap();
+    let roles: Vec<LayerRole> = overlay.layers.iter().map(|l| l.role).collect();
+    assert_eq!(
+        roles,
+        vec![
+            LayerRole::Upper,
+            LayerRole::Lower,
+            LayerRole::Lower,
+            LayerRole::Work
+        ]
+    );
+    assert_eq!(overlay.layers[2].path, Path::new("/l:1"));
+    assert_eq!(overlay.writable_layer().unwrap().path, Path::new("/data/u"));
+    // an overlay without upper layer is read-only
+    let overlay = source("61 25 0:51 / /ro rw - overlay overlay rw,lowerdir=/l2:/l1").unwrap();
+    assert_eq!(overlay.writable_layer(), None);
+    let mergerfs = source(
+        "62 25 0:52 / /pool rw - fuse.mergerfs /mnt/disk1:/mnt/disk2=RO rw,user_id=0,group_id=0",
+    )
+    .unwrap();
+    assert_eq!(mergerfs.layers.len(), 2);
+    assert!(mergerfs.layers[0].writable);
+    assert!(!mergerfs.layers[1].writable);
+    assert_eq!(mergerfs.layers[1].path, Path::new("/mnt/disk2"));
+    let gocryptfs =
+        source("63 25 0:53 / /home/me/vault rw - fuse.gocryptfs /home/me/.vault rw").unwrap();
+    assert_eq!(gocryptfs.layers[0].role, LayerRole::Cipher);
+    assert!(source("64 25 8:1 / /home rw - ext4 /dev/sda1 rw").is_none());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_space_mount() {
+    let mount = |line: &str| {
+        let mut mount = Mount::from_mountinfo_line(line);
+        mount.stacked = StackedSource::from_mount_info(&mount.info);
+        mount
+    };
+    let mut mounts = vec![
+        mount("25 1 8:1 / / rw - ext4 /dev/sda1 rw"),
+        mount("40 25 8:2 / /data rw - ext4 /dev/sda2 rw"),
+        mount(
+            "60 25 0:50 / /merged rw - overlay overlay rw,lowerdir=/l,upperdir=/data/u,workdir=/data/w",
+        ),
+    ];
+    resolve_stacked_layers(&mut mounts, |_| None);
+    let stacked = mounts[2].stacked.as_ref().unwrap();
+    assert_eq!(stacked.layers[0].mount_id, Some(40));
+    assert_eq!(stacked.layers[1].mount_id, Some(25));
+    assert_eq!(mounts[2].space_mount(&mounts).info.id, Some(40));
+    assert_eq!(mounts[1].space_mount(&mounts).info.id, Some(40));
+}
@@ -20,6 +20,11 @@ pub enum StatsError {
     #[snafu(display("Unconsistent stats"))]
     Unconsistent,
 
+    /// The mount is covered by another one, so stating its mount point
+    /// would give the stats of the covering filesystem
+    #[snafu(display("Shadowed by another mount"))]
+    Shadowed,
+
     /// Options made us not even try
     #[snafu(display("Excluded"))]
     Excluded,
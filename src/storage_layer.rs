@@ -0,0 +1,113 @@
+use crate::*;
+
+/// The kind of a layer in the storage stack of a filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageLayerKind {
+    Filesystem,
+    /// an encrypted device (dm-crypt, LUKS)
+    Crypt,
+    /// a LVM logical volume
+    LogicalVolume,
+    /// another device mapper device (multipath, verity, etc.)
+    DeviceMapper,
+    /// a software RAID array
+    Raid,
+    /// a device cached by a faster one (bcache)
+    Cache,
+    /// a loop device, bound to a file
+    Loop,
+    Partition,
+    /// a RAM disk (zram)
+    Ram,
+    /// a physical (or virtual) disk
+    Disk,
+}
+
+/// A layer in the storage stack of a filesystem, eg the dm-crypt device
+/// between a filesystem and its LVM logical volume
+#[derive(Debug, Clone)]
+pub struct StorageLayer {
+    pub kind: StorageLayerKind,
+    /// the name of the device (eg "dm-0", "sda1"), or the type
+    /// of the filesystem for the filesystem layer
+    pub name: String,
+    /// the name of a device mapper device, as in /dev/mapper/
+    pub dm_name: Option<String>,
+    /// the distance to the filesystem: 0 for the filesystem, 1 for the
+    /// device(s) it's on, 2 for the devices those ones are built on, etc.
+    pub depth: usize,
+    /// the description of the disk, for the layers at the bottom
+    pub disk: Option<Disk>,
+    /// the kind of a device mapper device
+    #[cfg(target_os = "linux")]
+    pub dm_kind: Option<DmKind>,
+    /// whether a device mapper device is suspended
+    #[cfg(target_os = "linux")]
+    pub dm_suspended: Option<bool>,
+    /// the description of a software RAID array
+    #[cfg(target_os = "linux")]
+    pub md_array: Option<MdArray>,
+}
+
+impl Mount {
+    /// Tell whether the filesystem is on an encrypted device, at
+    /// any level of its storage stack
+    pub fn is_encrypted(&self) -> bool {
+        if self.layers.is_empty() {
+            return self.disk.as_ref().is_some_and(|disk| disk.crypted);
+        }
+        self.layers
+            .iter()
+            .any(|layer| layer.kind == StorageLayerKind::Crypt)
+    }
+    /// Tell whether the filesystem is stored only on SSD (and not,
+    /// for example, on a HDD with a SSD cache)
+    pub fn is_on_ssd(&self) -> bool {
+        let is_ssd = |disk: &Disk| disk.disk_type() == "SSD";
+        let mut disks = self
+            .layers
+            .iter()
+            .filter_map(|layer| layer.disk.as_ref())
+            .peekable();
+        if disks.peek().is_none() {
+            return self.disk.as_ref().is_some_and(is_ssd);
+        }
+        disks.all(is_ssd)
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_storage_layers() {
+    let layer =
+        |kind: StorageLayerKind, name: &str, depth: usize, rotational: Option<bool>| StorageLayer {
+            kind,
+            name: name.to_string(),
+            dm_name: None,
+            depth,
+            disk: rotational.map(|rotational| Disk {
+                rotational: Some(rotational),
+                removable: Some(false),
+                ..Disk::with_name(name)
+            }),
+            dm_kind: None,
+            dm_suspended: None,
+            md_array: None,
+        };
+    let mut mount = Mount {
+        layers: vec![
+            layer(StorageLayerKind::Filesystem, "ext4", 0, None),
+            layer(StorageLayerKind::Crypt, "dm-1", 1, None),
+            layer(StorageLayerKind::LogicalVolume, "dm-0", 2, None),
+            layer(StorageLayerKind::Partition, "nvme0n1p2", 3, None),
+            layer(StorageLayerKind::Disk, "nvme0n1", 4, Some(false)),
+        ],
+        ..Mount::from_mountinfo_line("40 25 253:1 / /home rw - ext4 /dev/mapper/luks-home rw")
+    };
+    assert!(mount.is_encrypted());
+    assert!(mount.is_on_ssd());
+    mount.layers.remove(1);
+    mount.layers[3].disk.as_mut().unwrap().rotational = Some(true);
+    assert!(!mount.is_encrypted());
+    assert!(!mount.is_on_ssd());
+}
@@ -26,19 +26,158 @@ pub fn read_file_as_bool<P: AsRef<Path>>(path: P) -> Option<bool> {
     })
 }
 
-/// decode ascii-octal or ascii-hexa encoded strings
+/// iterate over the lines of a file read as bytes, skipping blank ones
 #[cfg(target_os = "linux")]
-pub fn decode_string<S: AsRef<str>>(s: S) -> String {
-    use lazy_regex::*;
-    // replacing octal escape sequences
-    let s = regex_replace_all!(r#"\\0(\d\d)"#, s.as_ref(), |_, n: &str| {
-        let c = u8::from_str_radix(n, 8).unwrap() as char;
-        c.to_string()
-    });
-    // replacing hexa escape sequences
-    let s = regex_replace_all!(r#"\\x([0-9a-fA-F]{2})"#, &s, |_, n: &str| {
-        let c = u8::from_str_radix(n, 16).unwrap() as char;
-        c.to_string()
-    });
-    s.to_string()
+pub fn non_blank_lines(content: &[u8]) -> impl Iterator<Item = &[u8]> {
+    content
+        .split(|&b| b == b'\n')
+        .filter(|line| line.iter().any(|b| !b.is_ascii_whitespace()))
+}
+
+/// split a line into its fields, separated by ascii whitespaces
+#[cfg(target_os = "linux")]
+pub fn split_fields(line: &[u8]) -> impl Iterator<Item = &[u8]> {
+    line.split(|b| b.is_ascii_whitespace())
+        .filter(|field| !field.is_empty())
+}
+
+/// decode ascii-octal (eg `\040`) or ascii-hexa (eg `\x20`) escape
+/// sequences at the byte level, so that multi-byte chars and non UTF-8
+/// names are preserved
+#[cfg(target_os = "linux")]
+pub fn decode_bytes(s: &[u8]) -> Vec<u8> {
+    fn octal(b: u8) -> Option<u8> {
+        (b'0'..=b'7').contains(&b).then(|| b - b'0')
+    }
+    fn hexa(b: u8) -> Option<u8> {
+        (b as char).to_digit(16).map(|d| d as u8)
+    }
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'\\' {
+            let escaped = match s.get(i + 1..i + 4) {
+                Some(&[b'x', h, l]) => hexa(h).zip(hexa(l)).map(|(h, l)| h << 4 | l),
+                Some(&[a, b, c]) if a <= b'3' => octal(a)
+                    .zip(octal(b))
+                    .zip(octal(c))
+                    .map(|((a, b), c)| a << 6 | b << 3 | c),
+                _ => None,
+            };
+            if let Some(byte) = escaped {
+                decoded.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        decoded.push(s[i]);
+        i += 1;
+    }
+    decoded
+}
+
+/// decode ascii-octal or ascii-hexa encoded names, preserving
+/// non UTF-8 bytes
+#[cfg(target_os = "linux")]
+pub fn decode_os_string<B: AsRef<[u8]>>(s: B) -> std::ffi::OsString {
+    use std::os::unix::ffi::OsStringExt;
+    std::ffi::OsString::from_vec(decode_bytes(s.as_ref()))
+}
+
+/// decode ascii-octal or ascii-hexa encoded strings, replacing
+/// invalid UTF-8 sequences
+#[cfg(target_os = "linux")]
+pub fn decode_string<B: AsRef<[u8]>>(s: B) -> String {
+    String::from_utf8_lossy(&decode_bytes(s.as_ref())).into_owned()
+}
+
+/// encode the chars which can't be found as is in the fields of
+/// /proc/self/mountinfo or fstab (space, tab, newline, backslash)
+/// as ascii-octal escape sequences
+pub fn encode_string<S: AsRef<str>>(s: S) -> String {
+    encode_chars(s.as_ref(), &[' ', '\t', '\n', '\\'])
+}
+
+/// encode the name of a mount option, escaping also the `,` and `=`
+/// separators, as the kernel does
+pub fn encode_option_name<S: AsRef<str>>(s: S) -> String {
+    encode_chars(s.as_ref(), &[' ', '\t', '\n', '\\', ',', '='])
+}
+
+/// encode the value of a mount option, escaping also the `,` separator,
+/// as the kernel does
+pub fn encode_option_value<S: AsRef<str>>(s: S) -> String {
+    encode_chars(s.as_ref(), &[' ', '\t', '\n', '\\', ','])
+}
+
+fn encode_chars(
+    s: &str,
+    special: &[char],
+) -> String {
+    let mut encoded = String::new();
+    for c in s.chars() {
+        if special.contains(&c) {
+            encoded.push_str(&format!("\\{:03o}", c as u8));
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// encode a path for /proc/self/mountinfo or fstab: special chars
+/// and bytes which aren't valid UTF-8 are written as ascii-octal
+/// escape sequences
+#[cfg(unix)]
+pub fn encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let mut bytes = path.as_os_str().as_bytes();
+    let mut encoded = String::new();
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                encoded.push_str(&encode_string(valid));
+                return encoded;
+            }
+            Err(e) => {
+                let (valid, invalid) = bytes.split_at(e.valid_up_to());
+                encoded.push_str(&encode_string(std::str::from_utf8(valid).unwrap()));
+                let invalid_len = e.error_len().unwrap_or(invalid.len());
+                for b in &invalid[..invalid_len] {
+                    encoded.push_str(&format!("\\{b:03o}"));
+                }
+                bytes = &invalid[invalid_len..];
+            }
+        }
+    }
+}
+
+/// encode a path for /proc/self/mountinfo or fstab
+#[cfg(not(unix))]
+pub fn encode_path(path: &Path) -> String {
+    encode_string(path.to_string_lossy())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_decode_encode() {
+    use std::{
+        ffi::OsStr,
+        os::unix::ffi::OsStrExt,
+    };
+    assert_eq!(
+        decode_string(r"/media/dys/USB\040DISK"),
+        "/media/dys/USB DISK"
+    );
+    assert_eq!(decode_string(r"a\134b\011c\012d"), "a\\b\tc\nd");
+    assert_eq!(decode_string(r"\xe7\x94\xa8\xe6\x88\xb7"), "用户");
+    assert_eq!(decode_string("/media/用户/USB"), "/media/用户/USB");
+    assert_eq!(decode_string(r"\0 \9999 \x4"), r"\0 \9999 \x4");
+    let raw = decode_os_string(r"/mnt/\377\376");
+    assert_eq!(raw.as_bytes(), b"/mnt/\xff\xfe");
+    assert_eq!(encode_path(Path::new(&raw)), r"/mnt/\377\376");
+    assert_eq!(
+        encode_path(Path::new(OsStr::from_bytes(b"/a b\\/\xc3\xa9\xff"))),
+        r"/a\040b\134/é\377"
+    );
 }
@@ -0,0 +1,478 @@
+use {
+    crate::*,
+    std::path::PathBuf,
+};
+
+/// Whether a mount is read-only or read-write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessMode {
+    ReadOnly,
+    ReadWrite,
+}
+
+/// Mount options restricting what can be done with the files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SecurityFlags {
+    /// set-user-ID and set-group-ID bits are ignored
+    pub nosuid: bool,
+    /// device files aren't interpreted
+    pub nodev: bool,
+    /// files can't be executed
+    pub noexec: bool,
+}
+
+/// How the access time of files is updated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtimePolicy {
+    /// updated only when older than the modification time, or than a day
+    Relatime,
+    /// never updated
+    Noatime,
+    /// updated on every access
+    Strictatime,
+}
+
+/// What the filesystem does on error (`errors=`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorsBehavior {
+    Continue,
+    RemountRo,
+    Panic,
+    Other(String),
+}
+
+/// Typed view of the options of a mount.
+///
+/// Options which aren't interpreted here are kept in `unknown`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypedMountOptions {
+    /// None when neither `ro` nor `rw` is present
+    pub access: Option<AccessMode>,
+    pub security: SecurityFlags,
+    /// None when no atime option is present
+    pub atime: Option<AtimePolicy>,
+    pub nodiratime: bool,
+    pub lazytime: bool,
+    /// writes are synchronous
+    pub sync: bool,
+    /// the mount uses an id mapping (kernel 5.12+)
+    pub idmapped: bool,
+    pub errors: Option<ErrorsBehavior>,
+    /// options specific to the filesystem type
+    pub fs: FsOptions,
+    /// options not interpreted in this typed view
+    pub unknown: Vec<MountOption>,
+}
+
+/// Options specific to a filesystem type
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FsOptions {
+    Btrfs(BtrfsOptions),
+    Ext4(Ext4Options),
+    Xfs(XfsOptions),
+    Nfs(NfsOptions),
+    Tmpfs(TmpfsOptions),
+    Overlay(OverlayOptions),
+    /// a filesystem type without specific typed options
+    #[default]
+    Other,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BtrfsOptions {
+    pub subvol: Option<String>,
+    pub subvolid: Option<u64>,
+    /// eg "zstd:3"
+    pub compress: Option<String>,
+    pub compress_force: bool,
+    pub ssd: bool,
+    /// eg "async", when discard is enabled
+    pub discard: Option<String>,
+    /// eg "v2"
+    pub space_cache: Option<String>,
+    pub autodefrag: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ext4Options {
+    /// eg "ordered", "journal", "writeback"
+    pub data: Option<String>,
+    pub barrier: Option<bool>,
+    pub discard: bool,
+    /// commit interval, in seconds
+    pub commit: Option<u32>,
+    pub stripe: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct XfsOptions {
+    pub attr2: bool,
+    pub inode64: bool,
+    pub logbufs: Option<u32>,
+    /// eg "32k"
+    pub logbsize: Option<String>,
+    pub sunit: Option<u32>,
+    pub swidth: Option<u32>,
+    pub usrquota: bool,
+    pub grpquota: bool,
+    pub prjquota: bool,
+    pub noquota: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NfsOptions {
+    /// eg "4.2"
+    pub vers: Option<String>,
+    /// eg "tcp"
+    pub proto: Option<String>,
+    /// server address
+    pub addr: Option<String>,
+    pub clientaddr: Option<String>,
+    pub rsize: Option<u64>,
+    pub wsize: Option<u64>,
+    /// timeout, in tenths of a second
+    pub timeo: Option<u32>,
+    pub retrans: Option<u32>,
+    /// Some(true) for "hard", Some(false) for "soft"
+    pub hard: Option<bool>,
+    /// eg "sys", "krb5"
+    pub sec: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TmpfsOptions {
+    /// eg "3071996k"
+    pub size: Option<String>,
+    pub nr_inodes: Option<u64>,
+    /// permissions of the root directory
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverlayOptions {
+    /// lower layers, the uppermost first
+    pub lowerdir: Vec<PathBuf>,
+    pub upperdir: Option<PathBuf>,
+    pub workdir: Option<PathBuf>,
+}
+
+impl TypedMountOptions {
+    /// Build the typed view of the options of a mount of the given type
+    pub fn new<'o, I: IntoIterator<Item = &'o MountOption>>(
+        fs_type: &str,
+        options: I,
+    ) -> Self {
+        let mut typed = Self {
+            fs: FsOptions::for_fs_type(fs_type),
+            ..Default::default()
+        };
+        for option in options {
+            if !typed.apply_generic(option) && !typed.fs.apply(option) {
+                typed.unknown.push(option.clone());
+            }
+        }
+        typed
+    }
+    /// tell whether the mount is read-only
+    pub fn is_read_only(&self) -> bool {
+        self.access == Some(AccessMode::ReadOnly)
+    }
+    fn apply_generic(
+        &mut self,
+        option: &MountOption,
+    ) -> bool {
+        match (option.name.as_str(), option.value.as_deref()) {
+            ("ro", None) => self.access = Some(AccessMode::ReadOnly),
+            ("rw", None) => self.access = Some(AccessMode::ReadWrite),
+            ("nosuid", None) => self.security.nosuid = true,
+            ("suid", None) => self.security.nosuid = false,
+            ("nodev", None) => self.security.nodev = true,
+            ("dev", None) => self.security.nodev = false,
+            ("noexec", None) => self.security.noexec = true,
+            ("exec", None) => self.security.noexec = false,
+            ("relatime", None) => self.atime = Some(AtimePolicy::Relatime),
+            ("noatime", None) => self.atime = Some(AtimePolicy::Noatime),
+            ("strictatime", None) => self.atime = Some(AtimePolicy::Strictatime),
+            ("nodiratime", None) => self.nodiratime = true,
+            ("lazytime", None) => self.lazytime = true,
+            ("sync" | "synchronous", None) => self.sync = true,
+            ("idmapped", None) => self.idmapped = true,
+            ("errors", Some(v)) => {
+                self.errors = Some(match v {
+                    "continue" => ErrorsBehavior::Continue,
+                    "remount-ro" => ErrorsBehavior::RemountRo,
+                    "panic" => ErrorsBehavior::Panic,
+                    _ => ErrorsBehavior::Other(v.to_string()),
+                });
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl FsOptions {
+    pub fn for_fs_type(fs_type: &str) -> Self {
+        match fs_type {
+            "btrfs" => Self::Btrfs(Default::default()),
+            "ext2" | "ext3" | "ext4" => Self::Ext4(Default::default()),
+            "xfs" => Self::Xfs(Default::default()),
+            "nfs" | "nfs4" => Self::Nfs(Default::default()),
+            "tmpfs" => Self::Tmpfs(Default::default()),
+            "overlay" | "fuse.fuse-overlayfs" => Self::Overlay(Default::default()),
+            _ => Self::Other,
+        }
+    }
+    /// apply the option, returning false if it's not known
+    fn apply(
+        &mut self,
+        option: &MountOption,
+    ) -> bool {
+        let name = option.name.as_str();
+        let value = option.value.as_deref();
+        match self {
+            Self::Btrfs(o) => o.apply(name, value),
+            Self::Ext4(o) => o.apply(name, value),
+            Self::Xfs(o) => o.apply(name, value),
+            Self::Nfs(o) => o.apply(name, value),
+            Self::Tmpfs(o) => o.apply(name, value),
+            Self::Overlay(o) => o.apply(name, value),
+            Self::Other => false,
+        }
+    }
+}
+
+/// parse the value into the target, returning false if it's not valid
+fn parse_into<T: std::str::FromStr>(
+    value: &str,
+    target: &mut Option<T>,
+) -> bool {
+    match value.parse() {
+        Ok(v) => {
+            *target = Some(v);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+impl BtrfsOptions {
+    fn apply(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+    ) -> bool {
+        match (name, value) {
+            ("subvol", Some(v)) => self.subvol = Some(v.to_string()),
+            ("subvolid", Some(v)) => return parse_into(v, &mut self.subvolid),
+            ("compress", Some(v)) => self.compress = Some(v.to_string()),
+            ("compress-force", Some(v)) => {
+                self.compress = Some(v.to_string());
+                self.compress_force = true;
+            }
+            ("ssd", None) => self.ssd = true,
+            ("discard", None) => self.discard = Some("sync".to_string()),
+            ("discard", Some(v)) => self.discard = Some(v.to_string()),
+            ("space_cache", None) => self.space_cache = Some("v1".to_string()),
+            ("space_cache", Some(v)) => self.space_cache = Some(v.to_string()),
+            ("autodefrag", None) => self.autodefrag = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Ext4Options {
+    fn apply(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+    ) -> bool {
+        match (name, value) {
+            ("data", Some(v)) => self.data = Some(v.to_string()),
+            ("barrier", None) => self.barrier = Some(true),
+            ("nobarrier", None) => self.barrier = Some(false),
+            ("barrier", Some(v)) => self.barrier = Some(v != "0"),
+            ("discard", None) => self.discard = true,
+            ("commit", Some(v)) => return parse_into(v, &mut self.commit),
+            ("stripe", Some(v)) => return parse_into(v, &mut self.stripe),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl XfsOptions {
+    fn apply(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+    ) -> bool {
+        match (name, value) {
+            ("attr2", None) => self.attr2 = true,
+            ("inode64", None) => self.inode64 = true,
+            ("logbufs", Some(v)) => return parse_into(v, &mut self.logbufs),
+            ("logbsize", Some(v)) => self.logbsize = Some(v.to_string()),
+            ("sunit", Some(v)) => return parse_into(v, &mut self.sunit),
+            ("swidth", Some(v)) => return parse_into(v, &mut self.swidth),
+            ("usrquota" | "uquota", None) => self.usrquota = true,
+            ("grpquota" | "gquota", None) => self.grpquota = true,
+            ("prjquota" | "pquota", None) => self.prjquota = true,
+            ("noquota", None) => self.noquota = true,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl NfsOptions {
+    fn apply(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+    ) -> bool {
+        match (name, value) {
+            ("vers" | "nfsvers", Some(v)) => self.vers = Some(v.to_string()),
+            ("proto", Some(v)) => self.proto = Some(v.to_string()),
+            ("addr", Some(v)) => self.addr = Some(v.to_string()),
+            ("clientaddr", Some(v)) => self.clientaddr = Some(v.to_string()),
+            ("rsize", Some(v)) => return parse_into(v, &mut self.rsize),
+            ("wsize", Some(v)) => return parse_into(v, &mut self.wsize),
+            ("timeo", Some(v)) => return parse_into(v, &mut self.timeo),
+            ("retrans", Some(v)) => return parse_into(v, &mut self.retrans),
+            ("hard", None) => self.hard = Some(true),
+            ("soft", None) => self.hard = Some(false),
+            ("sec", Some(v)) => self.sec = Some(v.to_string()),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl TmpfsOptions {
+    fn apply(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+    ) -> bool {
+        match (name, value) {
+            ("size", Some(v)) => self.size = Some(v.to_string()),
+            ("nr_inodes", Some(v)) => return parse_into(v, &mut self.nr_inodes),
+            ("mode", Some(v)) => match u32::from_str_radix(v, 8) {
+                Ok(mode) => self.mode = Some(mode),
+                Err(_) => return false,
+            },
+            ("uid", Some(v)) => return parse_into(v, &mut self.uid),
+            ("gid", Some(v)) => return parse_into(v, &mut self.gid),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl OverlayOptions {
+    fn apply(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+    ) -> bool {
+        match (name, value) {
+            ("lowerdir", Some(v)) => self.lowerdir = split_layers(v),
+            // layers added one by one (since linux 6.8)
+            ("lowerdir+" | "datadir+", Some(v)) => self.lowerdir.push(PathBuf::from(v)),
+            ("upperdir", Some(v)) => self.upperdir = Some(PathBuf::from(v)),
+            ("workdir", Some(v)) => self.workdir = Some(PathBuf::from(v)),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// split a list of layers separated by ':', where a ':' in a path
+/// is escaped as '\:', and data-only layers are after a '::'
+fn split_layers(value: &str) -> Vec<PathBuf> {
+    let mut layers = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.extend(chars.next()),
+            ':' => {
+                if !current.is_empty() {
+                    layers.push(PathBuf::from(std::mem::take(&mut current)));
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        layers.push(PathBuf::from(current));
+    }
+    layers
+}
+
+#[test]
+fn test_typed_options() {
+    let options = |s: &str| -> Vec<MountOption> {
+        s.split(',')
+            .map(|o| match o.split_once('=') {
+                Some((name, value)) => MountOption::new(name, Some(value)),
+                None => MountOption::new(o, None),
+            })
+            .collect()
+    };
+
+    let typed = TypedMountOptions::new(
+        "btrfs",
+        &options(
+            "ro,nosuid,noatime,compress=zstd:3,ssd,space_cache=v2,subvolid=256,subvol=/root,seclabel",
+        ),
+    );
+    assert!(typed.is_read_only());
+    assert!(typed.security.nosuid);
+    assert!(!typed.security.noexec);
+    assert_eq!(typed.atime, Some(AtimePolicy::Noatime));
+    let FsOptions::Btrfs(btrfs) = &typed.fs else {
+        panic!("btrfs options expected");
+    };
+    assert_eq!(btrfs.compress.as_deref(), Some("zstd:3"));
+    assert_eq!(btrfs.subvolid, Some(256));
+    assert_eq!(btrfs.subvol.as_deref(), Some("/root"));
+    assert!(btrfs.ssd);
+    assert_eq!(typed.unknown, vec![MountOption::new("seclabel", None)]);
+
+    let typed = TypedMountOptions::new(
+        "ext4",
+        &options("rw,relatime,errors=remount-ro,data=ordered,commit=abc"),
+    );
+    assert_eq!(typed.access, Some(AccessMode::ReadWrite));
+    assert_eq!(typed.errors, Some(ErrorsBehavior::RemountRo));
+    let FsOptions::Ext4(ext4) = &typed.fs else {
+        panic!("ext4 options expected");
+    };
+    assert_eq!(ext4.data.as_deref(), Some("ordered"));
+    assert_eq!(typed.unknown, vec![MountOption::new("commit", Some("abc"))]);
+
+    let typed = TypedMountOptions::new(
+        "overlay",
+        &options("rw,lowerdir=/l2:/l1,upperdir=/u,workdir=/w,idmapped"),
+    );
+    assert!(typed.idmapped);
+    let FsOptions::Overlay(overlay) = &typed.fs else {
+        panic!("overlay options expected");
+    };
+    assert_eq!(
+        overlay.lowerdir,
+        vec![PathBuf::from("/l2"), PathBuf::from("/l1")]
+    );
+    assert_eq!(overlay.upperdir, Some(PathBuf::from("/u")));
+
+    let typed = TypedMountOptions::new("tmpfs", &options("rw,size=1024k,mode=1777,huge=never"));
+    let FsOptions::Tmpfs(tmpfs) = &typed.fs else {
+        panic!("tmpfs options expected");
+    };
+    assert_eq!(tmpfs.mode, Some(0o1777));
+    assert_eq!(typed.unknown, vec![MountOption::new("huge", Some("never"))]);
+}
@@ -218,22 +218,32 @@ impl Volume {
                 let info = MountInfo {
                     id: None,
                     parent: None,
+                    unique_id: None,
                     dev: DeviceId::from(serial_number),
                     root: mount_point.clone(),
                     mount_point,
-                    options: Vec::new(),
+                    mount_options: Vec::new(),
+                    super_options: Vec::new(),
                     fs: self.name.to_string(),
                     fs_type: file_system_name.clone(),
+                    nodev: None,
                     bound: false,
+                    bound_reason: None,
+                    bind_source: None,
+                    propagation: Default::default(),
+                    shadowed: false,
                 };
 
                 Mount {
                     info,
-                    fs_label: Some(label.clone()),
+                    fs_label: Some(OsString::from(label)),
                     disk: disk.clone(),
                     stats: stats.clone(),
                     uuid: self.name.to_uuid(),
                     part_uuid: None,
+                    stacked: None,
+                    backing_disks: Vec::new(),
+                    layers: Vec::new(),
                 }
             })
             .collect())